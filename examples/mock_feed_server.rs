@@ -0,0 +1,211 @@
+//! Stand-in for the model server's market data feed, for working on the streaming code offline.
+//!
+//! Serves the WebSocket feed on `/ws` and the SSE fallback on `/stream?symbols=A,B`, pushing a
+//! random walk for every subscribed symbol: a tick every 250 ms and a new bar every 5 s.
+//!
+//! ```sh
+//! cargo run --example mock_feed_server -- 127.0.0.1:3001
+//! ```
+//!
+//! Then set the backend URL to `http://127.0.0.1:3001` and enable streaming in the settings.
+//! Kill it while the app is running to watch it reconnect.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde_json::json;
+use tungstenite::Message;
+
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+const BAR_SECONDS: i64 = 5;
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:3001".to_owned());
+    let listener = TcpListener::bind(&address)?;
+    println!("Mock feed listening on {address} (ws: /ws, sse: /stream?symbols=...)");
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        std::thread::spawn(move || {
+            if let Err(e) = handle(stream) {
+                println!("connection ended: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream) -> Result<(), String> {
+    // peek so tungstenite still sees the whole handshake
+    let mut head = [0u8; 512];
+    let n = stream.peek(&mut head).map_err(|e| e.to_string())?;
+    let request_line = String::from_utf8_lossy(&head[..n]).lines().next().unwrap_or_default().to_owned();
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_owned();
+    println!("{request_line}");
+
+    if path.starts_with("/ws") {
+        serve_websocket(stream)
+    } else if let Some(query) = path.strip_prefix("/stream?symbols=") {
+        let symbols = query.split(',').filter(|s| !s.is_empty()).map(str::to_owned).collect();
+        serve_sse(stream, symbols)
+    } else if path.starts_with("/health") {
+        respond(stream, "200 OK", "text/plain", "ok")
+    } else {
+        respond(stream, "404 Not Found", "text/plain", "not found")
+    }
+}
+
+fn respond(mut stream: TcpStream, status: &str, content_type: &str, body: &str) -> Result<(), String> {
+    let mut request = [0u8; 4096];
+    let _ = stream.read(&mut request);
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn serve_websocket(stream: TcpStream) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    socket.get_ref().set_read_timeout(Some(TICK_INTERVAL)).map_err(|e| e.to_string())?;
+
+    let mut market = Market::default();
+    let mut subscribed = BTreeSet::new();
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let command: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+                let symbols = command["symbols"].as_array().cloned().unwrap_or_default();
+                let symbols = symbols.iter().filter_map(|s| s.as_str()).map(str::to_owned);
+                match command["type"].as_str() {
+                    Some("subscribe") => subscribed.extend(symbols),
+                    Some("unsubscribe") => symbols.for_each(|s| {
+                        subscribed.remove(&s);
+                    }),
+                    _ => println!("unknown command {text}"),
+                }
+                println!("subscribed to {subscribed:?}");
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+            {
+                for message in market.step(&subscribed) {
+                    socket.send(Message::text(message.to_string())).map_err(|e| e.to_string())?;
+                }
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn serve_sse(mut stream: TcpStream, symbols: BTreeSet<String>) -> Result<(), String> {
+    let mut request = [0u8; 4096];
+    let _ = stream.read(&mut request);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n"
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut market = Market::default();
+    loop {
+        for message in market.step(&symbols) {
+            write!(stream, "data: {message}\n\n").map_err(|e| e.to_string())?;
+        }
+        stream.flush().map_err(|e| e.to_string())?;
+        std::thread::sleep(TICK_INTERVAL);
+    }
+}
+
+/// The bar currently forming for one symbol.
+struct OpenBar {
+    start: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+}
+
+/// A random walk per symbol, no `rand` needed.
+struct Market {
+    seed: u64,
+    steps: u64,
+    bars: HashMap<String, OpenBar>,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        let seed = Utc::now().timestamp_nanos_opt().unwrap_or(1) as u64 | 1;
+        Self { seed, steps: 0, bars: HashMap::new() }
+    }
+}
+
+impl Market {
+    fn random(&mut self) -> f64 {
+        // xorshift64
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed % 10_000) as f64 / 10_000.0
+    }
+
+    /// Moves every symbol one step. Sends a full bar when one opens (so the client has
+    /// something to attach ticks to) and about once a second to update its volume, a tick
+    /// otherwise.
+    fn step(&mut self, symbols: &BTreeSet<String>) -> Vec<serde_json::Value> {
+        let now = Utc::now();
+        let bar_start = now.duration_trunc(TimeDelta::seconds(BAR_SECONDS)).unwrap_or(now);
+        let mut messages = Vec::new();
+        self.steps += 1;
+        let send_bars = self.steps.is_multiple_of(4);
+
+        for symbol in symbols {
+            let drift = (self.random() - 0.5) * 0.4;
+            let volume = (self.random() * 500.0) as u64;
+            let first_price = 100.0 + self.random() * 100.0;
+            let is_new = self.bars.get(symbol).is_none_or(|bar| bar.start != bar_start);
+            if is_new {
+                let close = self.bars.get(symbol).map_or(first_price, |bar| bar.close);
+                self.bars.insert(symbol.clone(), OpenBar { start: bar_start, open: close, high: close, low: close, close, volume: 0 });
+            }
+
+            let bar = self.bars.get_mut(symbol).expect("inserted above");
+            bar.close = (bar.close + drift).max(0.01);
+            bar.high = bar.high.max(bar.close);
+            bar.low = bar.low.min(bar.close);
+            bar.volume += volume;
+
+            if is_new || send_bars {
+                messages.push(json!({
+                    "type": "bar",
+                    "symbol": symbol,
+                    "point": {
+                        "timestamp": bar.start,
+                        "open": bar.open,
+                        "high": bar.high,
+                        "low": bar.low,
+                        "close": bar.close,
+                        "volume": bar.volume,
+                    },
+                }));
+            } else {
+                messages.push(json!({
+                    "type": "tick",
+                    "symbol": symbol,
+                    "price": bar.close,
+                    "timestamp": now,
+                }));
+            }
+        }
+        messages
+    }
+}
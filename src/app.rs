@@ -1,10 +1,12 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+use std::{collections::{BTreeSet, HashMap, VecDeque}, path::Path, sync::{Arc, Mutex}, time::Duration};
 use egui::{Align, Color32, CornerRadius, FontData, FontDefinitions, FontFamily, Frame, Layout, Margin, RichText, Rounding, Stroke, Theme, Vec2, Visuals};
 
 use chrono::{DateTime, Utc};
-use rusty_trading_model::structs::{TimeSeries};
 
-use crate::{create_new_stock_window, Stock};
+use crate::{backend::BackendProfile, client::{HttpBackend, MockBackend, TradingBackend}, command::{self, OrderCommand}, connection::{self, ConnectionMonitor}, create_new_stock_window, hotkeys::{self, Action, Hotkeys, Quote}, layout, orders::{self, OrderBook, Side}, portfolio::{Portfolio, Position}, price::{InstrumentSpec, Instruments, Price}, stock::{Crosshair, FetchError}, stream::{FeedMessage, FeedUpdate, MarketFeed}, risk::RiskLimits, ticket::{OrderTicket, TradingContext}, Stock};
+
+/// How many failed fetches we keep around for the error log window.
+const MAX_FETCH_ERRORS: usize = 50;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -22,20 +24,76 @@ pub struct TemplateApp {
     last_update: DateTime<Utc>,
 
     stock: String,
-    qty: String,
-    price: String,
+    // Quick Trade, for any symbol without opening its window
+    quick_symbol: String,
+    quick_trade: OrderTicket,
+    // keyboard order entry, e.g. "buy 100 AAPL @ 175.5"
+    #[serde(skip)]
+    order_entry: String,
+    #[serde(skip)]
+    order_entry_ticket: OrderTicket,
+    #[serde(skip)]
+    focus_order_entry: bool,
+    hotkeys: Hotkeys,
+    // action waiting for its new key in the settings
+    #[serde(skip)]
+    rebinding: Option<Action>,
+    // stock window that was last in front, trading hotkeys go there
+    #[serde(skip)]
+    hotkey_stock: Option<String>,
     // TODO: Refactor this with DashMap?
     stocks_map: Arc<Mutex<HashMap<String, Arc<Mutex<Stock>>>>>,
 
+    backend: BackendProfile,
+    // simulated quotes in the stock windows instead of ones derived from the backend data
+    demo_mode: bool,
+    // every order sent from the app, kept across restarts
+    orders: Arc<Mutex<OrderBook>>,
+    risk: RiskLimits,
+    // tick and lot sizes for validating order prices and quantities
+    instruments: Instruments,
+    #[serde(skip)]
+    instrument_symbol: String,
+    #[serde(skip)]
+    client: Arc<dyn TradingBackend>,
+    #[serde(skip)]
+    use_mock_backend: bool,
+    // backend settings typed since the client was last rebuilt
+    #[serde(skip)]
+    backend_edited: bool,
+
     // New UI state fields
     #[serde(skip)]
-    connection_status: String,
+    connection: Arc<Mutex<ConnectionMonitor>>,
+    #[serde(skip)]
+    feed: MarketFeed,
+    // rebuilt from the order book's fills, which are what gets persisted
     #[serde(skip)]
-    total_portfolio_value: f64,
+    portfolio: Portfolio,
+    // shared by the stock windows' charts
     #[serde(skip)]
-    daily_pnl: f64,
+    crosshair: Crosshair,
+    // how the last layout save or load went
+    #[serde(skip)]
+    layout_message: Option<String>,
     #[serde(skip)]
     show_help: bool,
+    #[serde(skip)]
+    show_settings: bool,
+    #[serde(skip)]
+    show_order_entry: bool,
+    #[serde(skip)]
+    show_fetch_errors: bool,
+    #[serde(skip)]
+    show_orders: bool,
+    #[serde(skip)]
+    show_positions: bool,
+    // Orders window tab, open orders or history
+    #[serde(skip)]
+    show_order_history: bool,
+    // most recent first
+    #[serde(skip)]
+    fetch_errors: Arc<Mutex<VecDeque<FetchError>>>,
 }
 
 impl Default for TemplateApp {
@@ -47,13 +105,37 @@ impl Default for TemplateApp {
             value: Arc::new(Mutex::new(2.7)),
             last_update: Utc::now(),
             stock: String::new(),
-            qty: String::new(),
-            price: String::new(),
+            quick_symbol: String::new(),
+            quick_trade: OrderTicket::default(),
+            order_entry: String::new(),
+            order_entry_ticket: OrderTicket::default(),
+            focus_order_entry: false,
+            hotkeys: Hotkeys::default(),
+            rebinding: None,
+            hotkey_stock: None,
             stocks_map: Arc::new(Mutex::new(HashMap::new())),
-            connection_status: "Connected".to_owned(),
-            total_portfolio_value: 0.0,
-            daily_pnl: 0.0,
+            backend: BackendProfile::default(),
+            demo_mode: false,
+            orders: Arc::new(Mutex::new(OrderBook::default())),
+            risk: RiskLimits::default(),
+            instruments: Instruments::default(),
+            instrument_symbol: String::new(),
+            client: Arc::new(HttpBackend::new(BackendProfile::default())),
+            use_mock_backend: false,
+            backend_edited: false,
+            connection: Arc::new(Mutex::new(ConnectionMonitor::default())),
+            feed: MarketFeed::default(),
+            portfolio: Portfolio::default(),
+            crosshair: Crosshair::default(),
+            layout_message: None,
             show_help: false,
+            show_settings: false,
+            show_order_entry: false,
+            show_fetch_errors: false,
+            show_orders: false,
+            show_positions: false,
+            show_order_history: false,
+            fetch_errors: Arc::new(Mutex::new(VecDeque::new())),
         };
         app
     }
@@ -68,11 +150,28 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        app.backend.apply_overrides(cc);
+        app.rebuild_client();
+        for stock in app.stocks_map.lock().unwrap().values() {
+            stock.lock().unwrap().start_simulation(app.client.as_ref(), &cc.egui_ctx);
         }
+        app
+    }
 
-        Default::default()
+    /// Points the client at the current backend profile; call whenever the profile changes.
+    fn rebuild_client(&mut self) {
+        self.client = if self.use_mock_backend {
+            Arc::new(MockBackend::default())
+        } else {
+            Arc::new(HttpBackend::new(self.backend.clone()))
+        };
+        self.connection.lock().unwrap().reset();
+        self.feed.reset();
     }
 
     fn setup_custom_style(ctx: &egui::Context) {
@@ -136,12 +235,27 @@ impl eframe::App for TemplateApp {
         // Handle keyboard shortcuts
         self.handle_keyboard_shortcuts(ctx, frame);
 
-        // Update data periodically
+        // Probe the backend, and update data periodically (backing off while disconnected)
         let now = Utc::now();
-        if self.last_update + Duration::from_secs(1) <= now {
+        connection::probe_if_due(&self.connection, self.client.as_ref(), ctx);
+        let symbols: BTreeSet<String> = self
+            .stocks_map
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, stock)| stock.lock().unwrap().is_open())
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        let feed_update = self.feed.update(&symbols, &self.backend, ctx);
+        self.apply_feed_update(feed_update, ctx);
+        let poll_interval = self.connection.lock().unwrap().poll_interval();
+        if self.last_update + poll_interval <= now {
             self.update_market_data(ctx);
             self.last_update = now;
         }
+        orders::poll_open_orders(&self.orders, self.client.as_ref(), ctx);
+        orders::manage_brackets(&self.orders, self.client.as_ref(), ctx);
+        self.refresh_portfolio();
         ctx.request_repaint_after(Duration::from_millis(50));
 
         // Top menu bar with enhanced styling
@@ -161,7 +275,12 @@ impl eframe::App for TemplateApp {
                                     // TODO: Implement watchlist functionality
                                 }
                                 if ui.button("💾 Save Layout").clicked() {
-                                    // TODO: Implement layout saving
+                                    self.save_layout();
+                                    ui.close();
+                                }
+                                if ui.button("📂 Load Layout").clicked() {
+                                    self.load_layout(ctx);
+                                    ui.close();
                                 }
                                 ui.separator();
                                 if ui.button("❌ Quit").clicked() {
@@ -173,6 +292,14 @@ impl eframe::App for TemplateApp {
                         // View menu
                         ui.menu_button("View", |ui| {
                             ui.checkbox(&mut self.show_help, "📖 Show Help");
+                            ui.checkbox(&mut self.show_order_entry, "⌨ Order Entry");
+                            ui.checkbox(&mut self.show_settings, "⚙ Settings");
+                            ui.checkbox(&mut self.show_orders, "📋 Orders");
+                            ui.checkbox(&mut self.show_positions, "💼 Positions");
+                            ui.checkbox(&mut self.show_fetch_errors, "⚠ Fetch Errors");
+                            ui.separator();
+                            ui.checkbox(&mut self.demo_mode, "🎭 Demo Mode")
+                                .on_hover_text("Show simulated quotes instead of the backend's");
                         });
 
                         ui.add_space(26.0);
@@ -184,24 +311,36 @@ impl eframe::App for TemplateApp {
                         ui.separator();
 
                         // Connection status
-                        let status_color = if self.connection_status == "Connected" {
-                            Color32::from_rgb(0, 255, 0)
-                        } else {
-                            Color32::from_rgb(255, 0, 0)
+                        let connection = self.connection.lock().unwrap();
+                        let state = connection.state();
+                        let status = match connection.latency() {
+                            Some(latency) => format!("🔗 {} ({} ms)", state.label(), latency.as_millis()),
+                            None => format!("🔗 {}", state.label()),
                         };
-                        ui.label(RichText::new(format!("🔗 {}", self.connection_status)).color(status_color));
+                        let status_label = ui.label(RichText::new(status).color(state.color()));
+                        if let Some(error) = connection.last_error() {
+                            status_label.on_hover_text(format!("Last error: {error}"));
+                            ui.label(RichText::new(format!("⚠ {error}")).color(state.color()).small());
+                        }
+                        drop(connection);
+
+                        // Streaming feed status
+                        if self.backend.streaming {
+                            let feed_state = self.feed.state();
+                            let feed_label = ui.label(RichText::new(format!("📡 {}", feed_state.label())).color(feed_state.color()));
+                            if let Some(error) = self.feed.last_error() {
+                                feed_label.on_hover_text(format!("Last feed error: {error}"));
+                            }
+                        }
 
                         ui.separator();
 
                         // Portfolio summary
-                        ui.label(RichText::new(format!("💰 ${:.2}", self.total_portfolio_value)));
+                        ui.label(RichText::new(format!("💰 ${}", self.portfolio.total_value())));
 
-                        let pnl_color = if self.daily_pnl >= 0.0 {
-                            Color32::from_rgb(0, 255, 0)
-                        } else {
-                            Color32::from_rgb(255, 0, 0)
-                        };
-                        ui.label(RichText::new(format!("📈 {:.2}%", self.daily_pnl)).color(pnl_color));
+                        let daily_pnl = self.portfolio.daily_pnl();
+                        ui.label(RichText::new(format!("📈 {:.2}%", self.portfolio.daily_pnl_percent())).color(pnl_color(daily_pnl)))
+                            .on_hover_text(format!("Today: ${daily_pnl}"));
                     });
                 });
             });
@@ -212,7 +351,42 @@ impl eframe::App for TemplateApp {
                 ui.horizontal(|ui| {
                     ui.label(RichText::new(format!("⏰ {}", now.format("%H:%M:%S UTC"))).size(20.0));
                     ui.separator();
-                    ui.label(RichText::new(format!("📊 {} Active Positions", self.stocks_map.lock().unwrap().len())).size(18.0));
+                    ui.label(RichText::new(format!("📊 {} Active Positions", self.portfolio.open_positions().count())).size(18.0));
+                    ui.separator();
+                    ui.label(RichText::new(format!("👁 {} Watched", self.stocks_map.lock().unwrap().len())).size(18.0));
+
+                    if let Some(message) = &self.layout_message {
+                        ui.separator();
+                        ui.label(RichText::new(message).size(18.0));
+                    }
+
+                    if let Some(symbol) = &self.hotkey_stock {
+                        ui.separator();
+                        ui.label(RichText::new(format!("⌨ {symbol}")).size(18.0))
+                            .on_hover_text("Trading hotkeys go to this stock window");
+                    }
+
+                    let open_orders = self.orders.lock().unwrap().open_orders().count();
+                    if open_orders > 0 {
+                        ui.separator();
+                        let orders_button = ui.add(egui::Button::new(
+                            RichText::new(format!("📋 {open_orders} open orders")).size(18.0),
+                        ).frame(false));
+                        if orders_button.clicked() {
+                            self.show_orders = !self.show_orders;
+                        }
+                    }
+
+                    let error_count = self.fetch_errors.lock().unwrap().len();
+                    if error_count > 0 {
+                        ui.separator();
+                        let errors_button = ui.add(egui::Button::new(
+                            RichText::new(format!("⚠ {error_count} fetch errors")).color(Color32::from_rgb(255, 165, 0)),
+                        ).frame(false));
+                        if errors_button.clicked() {
+                            self.show_fetch_errors = !self.show_fetch_errors;
+                        }
+                    }
 
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                         if ui.small_button("❓").on_hover_text("Show keyboard shortcuts").clicked() {
@@ -239,39 +413,169 @@ impl eframe::App for TemplateApp {
         if self.show_help {
             self.show_help_window(ctx);
         }
+
+        // Settings window
+        if self.show_settings {
+            self.show_settings_window(ctx);
+        }
+
+        // Keyboard order entry
+        if self.show_order_entry {
+            self.show_order_entry_window(ctx);
+        }
+
+        // Quick Trade and order entry confirmations
+        let trading = TradingContext {
+            backend: self.client.as_ref(),
+            orders: &self.orders,
+            risk: &self.risk,
+            portfolio: &self.portfolio,
+            instruments: &self.instruments,
+        };
+        self.quick_trade.show_confirmation(ctx, egui::Id::new("quick_trade_confirm"), &trading);
+        self.order_entry_ticket.show_confirmation(ctx, egui::Id::new("order_entry_confirm"), &trading);
+
+        // Positions
+        if self.show_positions {
+            self.show_positions_window(ctx);
+        }
+
+        // Orders
+        if self.show_orders {
+            self.show_orders_window(ctx);
+        }
+
+        // Fetch error log
+        if self.show_fetch_errors {
+            self.show_fetch_errors_window(ctx);
+        }
     }
 }
 
 impl TemplateApp {
     fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        ctx.input(|i| {
-            if i.key_pressed(egui::Key::F1) {
-                self.show_help = !self.show_help;
+        // rebinding takes the next key, Escape gives up and Backspace unbinds
+        if let Some(action) = self.rebinding {
+            if let Some(shortcut) = hotkeys::captured_shortcut(ctx) {
+                match shortcut.logical_key {
+                    egui::Key::Escape => {}
+                    egui::Key::Backspace | egui::Key::Delete => self.hotkeys.bind(action, None),
+                    _ => self.hotkeys.bind(action, Some(shortcut)),
+                }
+                self.rebinding = None;
             }
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Q) {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+
+        for action in self.hotkeys.pressed(ctx) {
+            match action {
+                Action::ToggleHelp => self.show_help = !self.show_help,
+                Action::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                Action::OrderEntry => {
+                    self.show_order_entry = true;
+                    self.focus_order_entry = true;
+                }
+                Action::CancelAll => orders::cancel_all(&self.orders, self.client.as_ref(), ctx),
+                Action::BuyBid | Action::BuyAsk | Action::SellBid | Action::SellAsk => {
+                    if let Some((side, quote)) = action.order() {
+                        self.request_hotkey_order(side, quote);
+                    }
+                }
             }
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::N) {
-                // TODO: New stock picker shortcut
+        }
+    }
+
+    /// Opens the confirmation for a trading hotkey in the stock window last in front.
+    fn request_hotkey_order(&mut self, side: Side, quote: Quote) {
+        let stock = self.hotkey_stock.as_ref().and_then(|symbol| self.stocks_map.lock().unwrap().get(symbol).cloned());
+        let Some(stock) = stock else {
+            log::info!("No stock window for the {side} hotkey");
+            return;
+        };
+        let trading = TradingContext {
+            backend: self.client.as_ref(),
+            orders: &self.orders,
+            risk: &self.risk,
+            portfolio: &self.portfolio,
+            instruments: &self.instruments,
+        };
+        stock.lock().unwrap().request_quote_order(side, quote, self.hotkeys.order_qty, &trading);
+    }
+
+    fn apply_feed_update(&mut self, update: FeedUpdate, ctx: &egui::Context) {
+        if update.reconnected {
+            // catch up on whatever we missed while the feed was down
+            self.update_market_data(ctx);
+        }
+        let map = self.stocks_map.lock().unwrap();
+        for message in update.messages {
+            match message {
+                FeedMessage::Bar { symbol, point } => {
+                    if let Some(stock) = map.get(&symbol) {
+                        stock.lock().unwrap().merge_points([point]);
+                    }
+                }
+                FeedMessage::Tick { symbol, price, timestamp } => {
+                    if let Some(stock) = map.get(&symbol) {
+                        stock.lock().unwrap().apply_tick(price, timestamp);
+                    }
+                }
             }
-        });
+        }
     }
 
+    /// Polls every stock; while the streaming feed is live only stocks without any data yet.
     fn update_market_data(&mut self, ctx: &egui::Context) {
-        let ctx_clone = ctx.clone();
-        let mut map = self.stocks_map.lock().unwrap();
-        for (key, val) in map.iter_mut() {
-            let request_template = ehttp::Request::get(format!("http://127.0.0.1:3000/stock?stock={key}"));
+        let live = self.feed.is_live();
+        let map = self.stocks_map.lock().unwrap();
+        for (key, val) in map.iter() {
+            if live && val.lock().unwrap().has_data() {
+                continue;
+            }
             log::info!("now is {:?}", Utc::now());
             log::info!("calling get_stock api and repaint graph");
-            let val_clone = Arc::clone(&val);
-            ehttp::fetch(request_template, move |result: ehttp::Result<ehttp::Response>| {
-                let mut time_series: TimeSeries = serde_json::from_slice(&result.unwrap().bytes).unwrap();
-                log::info!("time series size {}", time_series.data().len());
-                val_clone.lock().unwrap().set_time_series(time_series);
-            });
+            self.fetch_stock(key, val, ctx);
+        }
+        ctx.request_repaint();
+    }
+
+    /// Fetches what's new for one symbol since its last point (everything on the first call).
+    /// On failure the previous data stays on screen and the error is recorded both on the stock
+    /// and in the app-wide log.
+    fn fetch_stock(&self, symbol: &str, stock: &Arc<Mutex<Stock>>, ctx: &egui::Context) {
+        let since = stock.lock().unwrap().last_point_time();
+        let stock = Arc::clone(stock);
+        let fetch_errors = Arc::clone(&self.fetch_errors);
+        let symbol_name = symbol.to_owned();
+        let ctx = ctx.clone();
+        self.client.fetch_time_series(symbol, since, Box::new(move |result| {
+            match result {
+                Ok(mut time_series) => {
+                    log::debug!("{symbol_name}: merging {} points", time_series.data().len());
+                    stock.lock().unwrap().merge_time_series(time_series);
+                }
+                Err(e) => {
+                    log::error!("Fetching time series for {symbol_name} failed: {e}");
+                    let error = FetchError::new(&symbol_name, &e);
+                    let mut log = fetch_errors.lock().unwrap();
+                    log.push_front(error.clone());
+                    log.truncate(MAX_FETCH_ERRORS);
+                    stock.lock().unwrap().set_fetch_error(error);
+                }
+            }
+            ctx.request_repaint();
+        }));
+    }
+
+    /// Picks up new fills and marks every position against its stock's latest close.
+    fn refresh_portfolio(&mut self) {
+        self.portfolio.sync(self.orders.lock().unwrap().fills());
+        let map = self.stocks_map.lock().unwrap();
+        let symbols: Vec<String> = self.portfolio.positions().map(|position| position.symbol.clone()).collect();
+        for symbol in symbols {
+            let mark = map.get(&symbol).and_then(|stock| stock.lock().unwrap().mark());
+            self.portfolio.set_mark(&symbol, mark);
         }
-        ctx_clone.request_repaint();
     }
 
     fn show_trading_panel(&mut self, ui: &mut egui::Ui) {
@@ -289,9 +593,11 @@ impl TemplateApp {
             ui.horizontal(|ui| {
                 if ui.button(RichText::new("➕ Add to Watchlist").size(12.0)).clicked() {
                     if !self.stock.is_empty() {
+                        let stock = Stock::default(&self.stock);
+                        stock.start_simulation(self.client.as_ref(), ui.ctx());
                         self.stocks_map.lock().unwrap().insert(
                             self.stock.clone(),
-                            Arc::new(Mutex::new(Stock::default(&self.stock)))
+                            Arc::new(Mutex::new(stock))
                         );
                         self.stock.clear();
                     }
@@ -305,27 +611,37 @@ impl TemplateApp {
         ui.group(|ui| {
             ui.label(RichText::new("⚡ Quick Trade").size(14.0).strong());
             ui.horizontal(|ui| {
-                ui.label("Qty:");
-                ui.text_edit_singleline(&mut self.qty);
+                ui.label("Symbol:");
+                ui.add(egui::TextEdit::singleline(&mut self.quick_symbol).desired_width(90.0).hint_text("any symbol"));
+                let mut watched: Vec<String> = self.stocks_map.lock().unwrap().keys().cloned().collect();
+                watched.sort();
+                egui::ComboBox::from_id_salt("quick_trade_symbol")
+                    .selected_text("Watched")
+                    .show_ui(ui, |ui| {
+                        if watched.is_empty() {
+                            ui.label(RichText::new("Nothing watched").color(Color32::GRAY));
+                        }
+                        for symbol in watched {
+                            ui.selectable_value(&mut self.quick_symbol, symbol.clone(), symbol);
+                        }
+                    });
             });
+            let symbol = self.quick_symbol.trim().to_owned();
+            // only watched symbols have a price
+            let mark = self.stocks_map.lock().unwrap().get(&symbol).and_then(|stock| stock.lock().unwrap().mark());
+            self.quick_trade.show_form(ui, "quick_trade_form", mark.map(|mark| mark.last), self.instruments.get(&symbol));
+            let trading = TradingContext {
+                backend: self.client.as_ref(),
+                orders: &self.orders,
+                risk: &self.risk,
+                portfolio: &self.portfolio,
+                instruments: &self.instruments,
+            };
             ui.horizontal(|ui| {
-                ui.label("Price:");
-                ui.text_edit_singleline(&mut self.price);
+                self.quick_trade.show_side_buttons(ui, &symbol, &trading);
             });
 
-            ui.horizontal(|ui| {
-                let buy_button = ui.add(egui::Button::new(RichText::new("BUY").color(Color32::WHITE))
-                    .fill(Color32::from_rgb(0, 150, 0)));
-                if buy_button.clicked() {
-                    // TODO: Implement quick buy
-                }
-
-                let sell_button = ui.add(egui::Button::new(RichText::new("SELL").color(Color32::WHITE))
-                    .fill(Color32::from_rgb(150, 0, 0)));
-                if sell_button.clicked() {
-                    // TODO: Implement quick sell
-                }
-            });
+            self.quick_trade.show_feedback(ui, &trading);
         });
 
         ui.add_space(10.0);
@@ -333,49 +649,573 @@ impl TemplateApp {
         // Portfolio summary
         ui.group(|ui| {
             ui.label(RichText::new("💼 Portfolio").size(14.0).strong());
-            ui.label(format!("Total Value: ${:.2}", self.total_portfolio_value));
-            ui.label(format!("Daily P&L: {:.2}%", self.daily_pnl));
-            ui.label(format!("Active Positions: {}", self.stocks_map.lock().unwrap().len()));
+            ui.label(format!("Total Value: ${}", self.portfolio.total_value()));
+            let daily_pnl = self.portfolio.daily_pnl();
+            ui.label(RichText::new(format!("Daily P&L: ${} ({:.2}%)", daily_pnl, self.portfolio.daily_pnl_percent())).color(pnl_color(daily_pnl)));
+            ui.label(format!("Unrealized: ${}", self.portfolio.unrealized_pnl()));
+            ui.label(format!("Realized: ${}", self.portfolio.realized_pnl()));
+            ui.label(format!("Active Positions: {}", self.portfolio.open_positions().count()));
+            if ui.button("💼 Positions").clicked() {
+                self.show_positions = !self.show_positions;
+            }
         });
     }
 
+    fn save_layout(&mut self) {
+        let path = Path::new(layout::LAYOUT_FILE);
+        let result = layout::save(&self.stocks_map.lock().unwrap(), path);
+        self.layout_message = Some(match result {
+            Ok(()) => format!("💾 Saved the layout to {}", path.display()),
+            Err(error) => format!("⚠ {error}"),
+        });
+    }
+
+    /// Replaces the stock windows with the saved ones. Symbols we're already watching keep
+    /// their data, only their charts change.
+    fn load_layout(&mut self, ctx: &egui::Context) {
+        let path = Path::new(layout::LAYOUT_FILE);
+        match layout::load(path) {
+            Ok(layouts) => {
+                let mut map = self.stocks_map.lock().unwrap();
+                map.retain(|symbol, _| layouts.contains_key(symbol));
+                for (symbol, chart) in layouts {
+                    if let Some(stock) = map.get(&symbol) {
+                        stock.lock().unwrap().apply_layout(chart);
+                        continue;
+                    }
+                    let mut stock = Stock::default(&symbol);
+                    stock.apply_layout(chart);
+                    stock.start_simulation(self.client.as_ref(), ctx);
+                    map.insert(symbol, Arc::new(Mutex::new(stock)));
+                }
+                self.hotkey_stock = self.hotkey_stock.take().filter(|symbol| map.contains_key(symbol));
+                self.layout_message = Some(format!("📂 Loaded the layout from {}", path.display()));
+            }
+            Err(error) => self.layout_message = Some(format!("⚠ {error}")),
+        }
+    }
+
     fn show_charts_area(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         if self.stocks_map.lock().unwrap().is_empty() {
             ui.centered_and_justified(|ui| {
                 ui.label(RichText::new("📊 Add a stock symbol to start trading").size(16.0).color(Color32::GRAY));
             });
         } else {
-            for (_, stock) in self.stocks_map.lock().unwrap().iter_mut() {
-                create_new_stock_window(&mut stock.lock().unwrap(), ctx);
+            let map = self.stocks_map.lock().unwrap();
+            let trading = TradingContext {
+                backend: self.client.as_ref(),
+                orders: &self.orders,
+                risk: &self.risk,
+                portfolio: &self.portfolio,
+                instruments: &self.instruments,
+            };
+            let mut retries = Vec::new();
+            let mut in_front = None;
+            self.crosshair.start_frame();
+            for (key, stock) in map.iter() {
+                let mut guard = stock.lock().unwrap();
+                if create_new_stock_window(&mut guard, ctx, &trading, self.demo_mode, &mut self.crosshair, &map) {
+                    in_front = Some(key.clone());
+                }
+                if guard.take_retry_request() {
+                    retries.push(key);
+                }
+            }
+            // the stock locks have to be released first, the mock backend answers synchronously
+            for key in retries {
+                self.fetch_stock(key, &map[key], ctx);
+            }
+            if in_front.is_some() {
+                self.hotkey_stock = in_front;
+            } else if self.hotkey_stock.as_ref().is_some_and(|symbol| !map.get(symbol).is_some_and(|stock| stock.lock().unwrap().is_open())) {
+                self.hotkey_stock = None;
             }
         }
     }
 
     fn show_help_window(&mut self, ctx: &egui::Context) {
+        let hotkeys = &self.hotkeys;
         egui::Window::new("📖 Keyboard Shortcuts")
             .open(&mut self.show_help)
             .show(ctx, |ui| {
                 ui.label(RichText::new("Keyboard Shortcuts").size(16.0).strong());
                 ui.separator();
 
+                egui::Grid::new("help_shortcuts_grid")
+                    .num_columns(2)
+                    .spacing([16.0, 4.0])
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            let shortcut = hotkeys.describe(ctx, action);
+                            ui.label(RichText::new(shortcut).monospace());
+                            ui.label(action.label());
+                            ui.end_row();
+                        }
+                    });
+                ui.label(RichText::new("Trading hotkeys go to the stock window last in front, change them in the settings")
+                    .color(Color32::GRAY).small());
+
+                ui.separator();
+                ui.label(RichText::new("Order Entry").size(14.0).strong());
+                for example in command::EXAMPLES {
+                    ui.label(RichText::new(example).monospace());
+                }
+                ui.label("• Enter confirms an order, Esc cancels it");
+
+                ui.separator();
+                ui.label(RichText::new("Mouse Controls").size(14.0).strong());
+                ui.label("• Drag to pan charts");
+                ui.label("• Scroll to zoom");
+                ui.label("• Right-click for context menu");
+            });
+    }
+
+    fn show_order_entry_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_order_entry;
+        egui::Window::new("⌨ Order Entry")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.order_entry)
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(f32::INFINITY)
+                    .hint_text(command::EXAMPLES[0]));
+                if std::mem::take(&mut self.focus_order_entry) {
+                    response.request_focus();
+                }
+                let (enter, escape) = ui.input(|i| (i.key_pressed(egui::Key::Enter), i.key_pressed(egui::Key::Escape)));
+                if response.lost_focus() && escape {
+                    self.show_order_entry = false;
+                }
+
+                let command = OrderCommand::parse(&self.order_entry);
+                match &command {
+                    Ok(command) => {
+                        ui.label(RichText::new(command.describe()).color(command.side.color()));
+                    }
+                    Err(message) if !self.order_entry.trim().is_empty() => {
+                        ui.label(RichText::new(message).color(Color32::GRAY));
+                    }
+                    Err(_) => {
+                        ui.label(RichText::new("Side, quantity, symbol, then @ price for a limit").color(Color32::GRAY));
+                    }
+                }
+
+                let trading = TradingContext {
+                    backend: self.client.as_ref(),
+                    orders: &self.orders,
+                    risk: &self.risk,
+                    portfolio: &self.portfolio,
+                    instruments: &self.instruments,
+                };
+                if let Some(command) = command.ok().filter(|_| response.lost_focus() && enter) {
+                    // only watched symbols have a price, which a market order needs
+                    let mark = self.stocks_map.lock().unwrap().get(&command.symbol).and_then(|stock| stock.lock().unwrap().mark());
+                    command.fill(&mut self.order_entry_ticket);
+                    self.order_entry_ticket.set_market_price(mark.map(|mark| mark.last), self.instruments.get(&command.symbol));
+                    self.order_entry_ticket.request(command.side, &command.symbol, &trading);
+                }
+                self.order_entry_ticket.show_feedback(ui, &trading);
+            });
+        self.show_order_entry &= open;
+    }
+
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let backend = &mut self.backend;
+        let risk = &mut self.risk;
+        let instruments = &mut self.instruments;
+        let instrument_symbol = &mut self.instrument_symbol;
+        let hotkeys = &mut self.hotkeys;
+        let rebinding = &mut self.rebinding;
+        let use_mock_backend = &mut self.use_mock_backend;
+        let backend_edited = &mut self.backend_edited;
+        let mut changed = false;
+        egui::Window::new("⚙ Settings")
+            .open(&mut self.show_settings)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("Backend").size(16.0).strong());
+                ui.separator();
+
+                egui::Grid::new("backend_settings_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Base URL:");
+                        changed |= backend_field(ui.add(egui::TextEdit::singleline(&mut backend.base_url).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("Auth header:");
+                        changed |= backend_field(ui.add(egui::TextEdit::singleline(&mut backend.auth_header_name).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("Auth value:");
+                        changed |= backend_field(ui.add(egui::TextEdit::singleline(&mut backend.auth_header_value).password(true).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("Health path:");
+                        changed |= backend_field(ui.add(egui::TextEdit::singleline(&mut backend.health_path).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("Streaming:");
+                        changed |= ui.checkbox(&mut backend.streaming, "Push market data over WebSocket / SSE").changed();
+                        ui.end_row();
+
+                        ui.label("WebSocket path:");
+                        changed |= backend_field(ui.add_enabled(backend.streaming, egui::TextEdit::singleline(&mut backend.websocket_path).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("SSE path:");
+                        changed |= backend_field(ui.add_enabled(backend.streaming, egui::TextEdit::singleline(&mut backend.sse_path).desired_width(240.0)), backend_edited);
+                        ui.end_row();
+
+                        ui.label("Timeout:");
+                        changed |= backend_field(ui.add(egui::DragValue::new(&mut backend.timeout_secs).range(1..=120).suffix(" s")), backend_edited);
+                        ui.end_row();
+                    });
+
+                if let Some(url) = backend.url_override().map(str::to_owned) {
+                    ui.separator();
+                    ui.label(
+                        RichText::new(format!("⚠ Overridden by {}: {url}", BackendProfile::override_source()))
+                            .color(Color32::from_rgb(255, 165, 0)),
+                    );
+                    if ui.button("Use saved URL").clicked() {
+                        backend.clear_url_override();
+                        changed = true;
+                    }
+                }
+
+                ui.separator();
+                changed |= ui
+                    .checkbox(use_mock_backend, "Use offline mock backend")
+                    .on_hover_text("Serve requests from an in-memory mock instead of the model server")
+                    .changed();
+                let target = if *use_mock_backend { "the offline mock".to_owned() } else { backend.base_url().to_owned() };
+                ui.label(RichText::new(format!("Requests go to {target}")).color(Color32::GRAY));
+
+                ui.add_space(8.0);
+                ui.label(RichText::new("Risk Limits").size(16.0).strong());
+                ui.separator();
+                ui.checkbox(&mut risk.enabled, "Check orders before confirming");
+                ui.add_enabled_ui(risk.enabled, |ui| {
+                    egui::Grid::new("risk_settings_grid")
+                        .num_columns(2)
+                        .spacing([12.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.label("Max order value:");
+                            price_drag_value(ui, &mut risk.max_order_notional);
+                            ui.end_row();
+
+                            ui.label("Max position:");
+                            ui.add(egui::DragValue::new(&mut risk.max_position).speed(10.0).suffix(" shares"));
+                            ui.end_row();
+
+                            ui.label("Price band:");
+                            ui.add(egui::DragValue::new(&mut risk.price_band_percent).range(0.0..=100.0).speed(0.1).suffix(" %"))
+                                .on_hover_text("How far limit and stop prices may be from the last price");
+                            ui.end_row();
+
+                            ui.label("Daily loss limit:");
+                            price_drag_value(ui, &mut risk.daily_loss_limit);
+                            ui.end_row();
+
+                            ui.label("Restricted:");
+                            ui.add(egui::TextEdit::singleline(&mut risk.restricted_symbols).desired_width(240.0).hint_text("e.g. GME, AMC"));
+                            ui.end_row();
+                        });
+                    ui.label(RichText::new("0 turns a limit off").color(Color32::GRAY).small());
+                });
+
+                ui.add_space(8.0);
+                ui.label(RichText::new("Instruments").size(16.0).strong());
+                ui.separator();
+                let mut removed = None;
+                egui::Grid::new("instrument_settings_grid")
+                    .num_columns(4)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label(RichText::new("Symbol").strong());
+                        ui.label(RichText::new("Tick").strong());
+                        ui.label(RichText::new("Lot").strong());
+                        ui.end_row();
+
+                        ui.label("Default");
+                        instrument_spec_fields(ui, &mut instruments.default);
+                        ui.end_row();
+
+                        for (symbol, spec) in instruments.overrides.iter_mut() {
+                            ui.label(symbol);
+                            instrument_spec_fields(ui, spec);
+                            if ui.small_button("🗑").clicked() {
+                                removed = Some(symbol.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(symbol) = removed {
+                    instruments.overrides.remove(&symbol);
+                }
                 ui.horizontal(|ui| {
-                    ui.label(RichText::new("F1").monospace());
-                    ui.label("Toggle this help window");
+                    ui.add(egui::TextEdit::singleline(instrument_symbol).desired_width(80.0).hint_text("Symbol"));
+                    let symbol = instrument_symbol.trim().to_uppercase();
+                    if ui.add_enabled(!symbol.is_empty(), egui::Button::new("➕ Override")).clicked() {
+                        let spec = instruments.default;
+                        instruments.overrides.entry(symbol).or_insert(spec);
+                        instrument_symbol.clear();
+                    }
                 });
+
+                ui.add_space(8.0);
+                ui.label(RichText::new("Hotkeys").size(16.0).strong());
+                ui.separator();
+                egui::Grid::new("hotkey_settings_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.label());
+                            let text = if *rebinding == Some(action) { "Press a key…".to_owned() } else { hotkeys.describe(ctx, action) };
+                            if ui.add(egui::Button::new(RichText::new(text).monospace()).min_size(Vec2::new(120.0, 0.0)))
+                                .on_hover_text("Click, then press the new key. Esc keeps the old one, Backspace unbinds")
+                                .clicked()
+                            {
+                                *rebinding = Some(action);
+                            }
+                            ui.end_row();
+                        }
+
+                        ui.label("Hotkey order size:");
+                        ui.add(egui::DragValue::new(&mut hotkeys.order_qty).range(1..=1_000_000).suffix(" shares"))
+                            .on_hover_text("Used when the stock window's ticket has no quantity");
+                        ui.end_row();
+                    });
+                if ui.button("↺ Default keys").clicked() {
+                    hotkeys.reset();
+                    *rebinding = None;
+                }
+            });
+
+        // a field still being typed in when the window closes
+        if changed || (!self.show_settings && self.backend_edited) {
+            self.backend_edited = false;
+            self.rebuild_client();
+        }
+    }
+
+    fn show_fetch_errors_window(&mut self, ctx: &egui::Context) {
+        let fetch_errors = Arc::clone(&self.fetch_errors);
+        egui::Window::new("⚠ Fetch Errors")
+            .open(&mut self.show_fetch_errors)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let mut log = fetch_errors.lock().unwrap();
                 ui.horizontal(|ui| {
-                    ui.label(RichText::new("Ctrl+Q").monospace());
-                    ui.label("Quit application");
+                    ui.label(RichText::new(format!("{} recent errors", log.len())).strong());
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.button("🗑 Clear").clicked() {
+                            log.clear();
+                        }
+                    });
                 });
+                ui.separator();
+
+                if log.is_empty() {
+                    ui.label(RichText::new("No errors 🎉").color(Color32::GRAY));
+                    return;
+                }
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("fetch_errors_grid")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for error in log.iter() {
+                                ui.label(RichText::new(error.at.format("%H:%M:%S").to_string()).monospace());
+                                ui.label(RichText::new(&error.symbol).strong());
+                                ui.label(RichText::new(&error.message).color(Color32::from_rgb(255, 100, 100)));
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+    }
+
+    fn show_orders_window(&mut self, ctx: &egui::Context) {
+        let book = Arc::clone(&self.orders);
+        let show_history = &mut self.show_order_history;
+        // cancels go out after the book is unlocked, the mock backend answers right away
+        let mut to_cancel = Vec::new();
+        let mut cancel_all = false;
+        egui::Window::new("📋 Orders")
+            .open(&mut self.show_orders)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let mut book = book.lock().unwrap();
+                let open_count = book.open_orders().count();
                 ui.horizontal(|ui| {
-                    ui.label(RichText::new("Ctrl+N").monospace());
-                    ui.label("New stock picker");
+                    ui.selectable_value(show_history, false, format!("Open ({open_count})"));
+                    ui.selectable_value(show_history, true, format!("History ({})", book.orders().len() - open_count));
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if *show_history {
+                            if ui.button("🗑 Clear").clicked() {
+                                book.clear_history();
+                            }
+                        } else if ui.add_enabled(open_count > 0, egui::Button::new("✖ Cancel All")).clicked() {
+                            cancel_all = true;
+                        }
+                    });
                 });
+                ui.separator();
+
+                let orders: Vec<_> = book.orders().iter().rev().filter(|order| order.status.is_open() != *show_history).collect();
+                if orders.is_empty() {
+                    ui.label(RichText::new("No orders").color(Color32::GRAY));
+                    return;
+                }
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("orders_grid")
+                        .num_columns(9)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for header in ["Time", "ID", "Symbol", "Side", "Type", "Qty", "Price", "Status", ""] {
+                                ui.label(RichText::new(header).strong());
+                            }
+                            ui.end_row();
+
+                            for order in orders {
+                                ui.label(RichText::new(order.created_at.format("%H:%M:%S").to_string()).monospace());
+                                let id_label = ui.label(RichText::new(&order.client_id).monospace());
+                                if let Some(server_id) = &order.server_id {
+                                    id_label.on_hover_text(format!("Server id: {server_id}"));
+                                }
+                                ui.label(RichText::new(&order.symbol).strong());
+                                ui.label(RichText::new(order.side.to_string()).color(order.side.color()));
+                                let type_text = match order.leg {
+                                    Some(leg) => format!("{} {} · {}", order.order_type.label(), order.time_in_force.label(), leg.label()),
+                                    None => format!("{} {}", order.order_type.label(), order.time_in_force.label()),
+                                };
+                                let type_label = ui.label(type_text);
+                                if let Some(stop) = order.stop_price {
+                                    type_label.on_hover_text(format!("Stop ${stop}"));
+                                }
+                                ui.label(format!("{}/{}", order.filled_qty, order.qty));
+                                let price = if order.filled_qty > 0 {
+                                    format!("${} (avg ${})", order.price, order.avg_fill_price)
+                                } else {
+                                    format!("${}", order.price)
+                                };
+                                ui.label(price);
+                                let status = ui.label(RichText::new(order.status.label()).color(order.status.color()));
+                                if let orders::OrderStatus::Rejected(reason) | orders::OrderStatus::Unconfirmed(reason) = &order.status {
+                                    status.on_hover_text(reason);
+                                }
+                                if order.status.is_open() {
+                                    let cancellable = order.server_id.is_some() && order.status != orders::OrderStatus::CancelPending;
+                                    let cancel = ui.add_enabled(cancellable, egui::Button::new("✖ Cancel").small())
+                                        .on_disabled_hover_text("The server hasn't given this order an id yet");
+                                    if cancel.clicked() {
+                                        to_cancel.push(order.client_id.clone());
+                                    }
+                                } else {
+                                    ui.label(RichText::new(order.updated_at.format("%H:%M:%S").to_string()).color(Color32::GRAY).small());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if cancel_all {
+            orders::cancel_all(&self.orders, self.client.as_ref(), ctx);
+        }
+        for client_id in to_cancel {
+            orders::cancel(&self.orders, self.client.as_ref(), ctx, &client_id);
+        }
+    }
 
+    fn show_positions_window(&mut self, ctx: &egui::Context) {
+        let portfolio = &self.portfolio;
+        egui::Window::new("💼 Positions")
+            .open(&mut self.show_positions)
+            .default_width(620.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("Value ${}", portfolio.total_value())).strong());
+                    ui.separator();
+                    ui.label(format!("Unrealized ${}", portfolio.unrealized_pnl()));
+                    ui.separator();
+                    ui.label(format!("Realized ${}", portfolio.realized_pnl()));
+                });
                 ui.separator();
-                ui.label(RichText::new("Mouse Controls").size(14.0).strong());
-                ui.label("• Drag to pan charts");
-                ui.label("• Scroll to zoom");
-                ui.label("• Right-click for context menu");
+
+                let positions: Vec<&Position> = portfolio.positions().collect();
+                if positions.is_empty() {
+                    ui.label(RichText::new("No positions, fills show up here").color(Color32::GRAY));
+                    return;
+                }
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("positions_grid")
+                        .num_columns(8)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for header in ["Symbol", "Qty", "Avg Cost", "Last", "Value", "Unrealized", "Realized", "Today"] {
+                                ui.label(RichText::new(header).strong());
+                            }
+                            ui.end_row();
+
+                            for position in positions {
+                                let symbol_color = if position.is_flat() { Color32::GRAY } else { Color32::WHITE };
+                                ui.label(RichText::new(&position.symbol).strong().color(symbol_color));
+                                ui.label(position.qty.to_string());
+                                ui.label(format!("${}", position.avg_cost()));
+                                match position.mark {
+                                    Some(mark) => ui.label(format!("${:.2}", mark.last)),
+                                    None => ui.label(RichText::new("—").color(Color32::GRAY))
+                                        .on_hover_text("Not watched, valued at cost"),
+                                };
+                                ui.label(format!("${}", position.market_value()));
+                                for pnl in [position.unrealized_pnl(), position.realized_pnl, position.daily_pnl()] {
+                                    ui.label(RichText::new(format!("${pnl}")).color(pnl_color(pnl)));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
             });
     }
 }
+
+/// Whether a backend setting should be applied, which is once the field is left rather than on
+/// every keystroke, so a half-typed URL never gets connected to.
+fn backend_field(response: egui::Response, edited: &mut bool) -> bool {
+    *edited |= response.changed();
+    *edited && (response.lost_focus() || response.drag_stopped())
+}
+
+/// Whole-dollar drag value for a money setting.
+fn price_drag_value(ui: &mut egui::Ui, price: &mut Price) {
+    let mut dollars = price.to_f64().round();
+    if ui.add(egui::DragValue::new(&mut dollars).range(0.0..=1e12).speed(100.0).prefix("$")).changed() {
+        *price = Price::from_cents(dollars as i64 * 100);
+    }
+}
+
+fn instrument_spec_fields(ui: &mut egui::Ui, spec: &mut InstrumentSpec) {
+    let mut tick = spec.tick_size.to_f64();
+    if ui.add(egui::DragValue::new(&mut tick).range(0.0001..=1000.0).speed(0.001).max_decimals(Price::DECIMALS as usize).prefix("$")).changed() {
+        spec.tick_size = Price::from_f64(tick);
+    }
+    ui.add(egui::DragValue::new(&mut spec.lot_size).range(1..=100_000).suffix(" shares"));
+}
+
+fn pnl_color(pnl: Price) -> Color32 {
+    if !pnl.is_negative() {
+        Color32::from_rgb(0, 255, 0)
+    } else {
+        Color32::from_rgb(255, 0, 0)
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+/// Environment variable overriding the persisted base URL on native.
+const BACKEND_URL_ENV: &str = "RUSTY_TRADING_BACKEND";
+/// Command line flag overriding the persisted base URL on native, `--backend <url>` or `--backend=<url>`.
+const BACKEND_URL_ARG: &str = "--backend";
+/// URL query parameter overriding the persisted base URL on the web, `?backend=<url>`.
+const BACKEND_URL_QUERY: &str = "backend";
+
+/// Where the model server lives and how to talk to it.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct BackendProfile {
+    pub base_url: String,
+    // the header is only sent when the value is non-empty
+    pub auth_header_name: String,
+    pub auth_header_value: String,
+    pub timeout_secs: u64,
+    // relative to the base URL, polled to drive the connection status
+    pub health_path: String,
+    // push market data over WebSocket (or SSE as a fallback) instead of only polling
+    pub streaming: bool,
+    pub websocket_path: String,
+    pub sse_path: String,
+
+    // set from env / CLI / query string at startup, never persisted
+    #[serde(skip)]
+    url_override: Option<String>,
+}
+
+impl Default for BackendProfile {
+    fn default() -> Self {
+        Self {
+            base_url: "http://127.0.0.1:3000".to_owned(),
+            auth_header_name: "Authorization".to_owned(),
+            auth_header_value: String::new(),
+            timeout_secs: 5,
+            health_path: "health".to_owned(),
+            streaming: false,
+            websocket_path: "ws".to_owned(),
+            sse_path: "stream".to_owned(),
+            url_override: None,
+        }
+    }
+}
+
+impl BackendProfile {
+    /// The base URL actually in use, taking any startup override into account.
+    pub fn base_url(&self) -> &str {
+        self.url_override.as_deref().unwrap_or(&self.base_url).trim_end_matches('/')
+    }
+
+    pub fn url_override(&self) -> Option<&str> {
+        self.url_override.as_deref()
+    }
+
+    pub fn clear_url_override(&mut self) {
+        self.url_override = None;
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url(), path.trim_start_matches('/'))
+    }
+
+    /// The WebSocket feed URL, same host as the base URL with a `ws`/`wss` scheme.
+    pub fn websocket_url(&self) -> String {
+        let url = self.url(&self.websocket_path);
+        if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            url
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    pub fn get(&self, path: &str) -> ehttp::Request {
+        let mut request = ehttp::Request::get(self.url(path));
+        self.authorize(&mut request);
+        request
+    }
+
+    pub fn post_json<T: serde::Serialize>(&self, path: &str, body: &T) -> serde_json::Result<ehttp::Request> {
+        let mut request = ehttp::Request::json(self.url(path), body)?;
+        self.authorize(&mut request);
+        Ok(request)
+    }
+
+    /// `(name, value)` if an auth header is configured.
+    pub fn auth_header(&self) -> Option<(String, String)> {
+        if self.auth_header_name.is_empty() || self.auth_header_value.is_empty() {
+            return None;
+        }
+        Some((self.auth_header_name.clone(), self.auth_header_value.clone()))
+    }
+
+    fn authorize(&self, request: &mut ehttp::Request) {
+        if let Some((name, value)) = self.auth_header() {
+            request.headers.insert(name, value);
+        }
+    }
+
+    /// Picks up a base URL override from the command line or environment (native)
+    /// or from the page's query string (web).
+    pub fn apply_overrides(&mut self, cc: &eframe::CreationContext<'_>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = cc;
+            let mut args = std::env::args().skip(1);
+            let mut from_args = None;
+            while let Some(arg) = args.next() {
+                if arg == BACKEND_URL_ARG {
+                    from_args = args.next();
+                } else if let Some(url) = arg.strip_prefix(BACKEND_URL_ARG).and_then(|rest| rest.strip_prefix('=')) {
+                    from_args = Some(url.to_owned());
+                }
+            }
+            let from_env = std::env::var(BACKEND_URL_ENV).ok();
+            self.url_override = from_args.or(from_env).filter(|url| !url.is_empty());
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.url_override = cc
+                .integration_info
+                .web_info
+                .location
+                .query_map
+                .get(BACKEND_URL_QUERY)
+                .and_then(|values| values.first())
+                .filter(|url| !url.is_empty())
+                .cloned();
+        }
+
+        if let Some(url) = &self.url_override {
+            log::info!("Using backend override {url}");
+        }
+    }
+
+    /// Where the override came from, for display in the settings window.
+    pub fn override_source() -> String {
+        if cfg!(target_arch = "wasm32") {
+            format!("?{BACKEND_URL_QUERY}= query parameter")
+        } else {
+            format!("{BACKEND_URL_ARG} argument or {BACKEND_URL_ENV}")
+        }
+    }
+}
+
+/// Percent-encodes `value` for a URL query, everything but letters, digits and `-._~`.
+pub fn encode_query(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
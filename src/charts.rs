@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+
+use crate::series::Candle;
+
+/// Most Renko bricks or point & figure boxes a chart is built from. A box that's tiny next to
+/// the moves would make millions of them every frame, so the chart stops there instead.
+pub const MAX_BOXES: usize = 20_000;
+
+/// How the prices are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ChartType {
+    #[default]
+    Candles,
+    OhlcBars,
+    HeikinAshi,
+    Line,
+    Area,
+    /// A line filled above and below the first close in the up and down colors.
+    Baseline,
+    Renko,
+    PointAndFigure,
+}
+
+impl ChartType {
+    pub const ALL: [ChartType; 8] = [
+        ChartType::Candles,
+        ChartType::OhlcBars,
+        ChartType::HeikinAshi,
+        ChartType::Line,
+        ChartType::Area,
+        ChartType::Baseline,
+        ChartType::Renko,
+        ChartType::PointAndFigure,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChartType::Candles => "🕯 Candles",
+            ChartType::OhlcBars => "┤ OHLC bars",
+            ChartType::HeikinAshi => "🕯 Heikin-Ashi",
+            ChartType::Line => "📈 Line",
+            ChartType::Area => "⛰ Area",
+            ChartType::Baseline => "⏤ Baseline",
+            ChartType::Renko => "▦ Renko",
+            ChartType::PointAndFigure => "✖ Point & figure",
+        }
+    }
+
+    /// Built from price moves of a box size rather than from time.
+    pub fn uses_boxes(&self) -> bool {
+        matches!(self, ChartType::Renko | ChartType::PointAndFigure)
+    }
+
+    /// What gets drawn, one candle per element: the candles themselves, Heikin-Ashi candles,
+    /// Renko bricks spread over `slot` ms of the bar that made them, or point and figure
+    /// columns from open to close.
+    pub fn bars<'a>(&self, candles: &'a [Candle], box_size: f64, slot: f64) -> Cow<'a, [Candle]> {
+        match self {
+            ChartType::HeikinAshi => Cow::Owned(heikin_ashi(candles)),
+            ChartType::Renko => Cow::Owned(renko(candles, box_size, slot)),
+            ChartType::PointAndFigure => Cow::Owned(point_and_figure(candles, box_size, 3)),
+            _ => Cow::Borrowed(candles),
+        }
+    }
+}
+
+/// Candles averaged with the ones before, which smooths out the noise in a trend.
+pub fn heikin_ashi(candles: &[Candle]) -> Vec<Candle> {
+    let mut smoothed: Vec<Candle> = Vec::with_capacity(candles.len());
+    for candle in candles {
+        let close = (candle.open + candle.high + candle.low + candle.close) / 4.0;
+        let open = match smoothed.last() {
+            Some(previous) => (previous.open + previous.close) / 2.0,
+            None => (candle.open + candle.close) / 2.0,
+        };
+        smoothed.push(Candle {
+            open,
+            close,
+            high: candle.high.max(open).max(close),
+            low: candle.low.min(open).min(close),
+            ..*candle
+        });
+    }
+    smoothed
+}
+
+/// A brick each time the close gets a box past the last brick, so turning around takes two.
+/// The bricks a bar makes share its `slot` and its volume. Stops at [`MAX_BOXES`] bricks.
+pub fn renko(candles: &[Candle], box_size: f64, slot: f64) -> Vec<Candle> {
+    let mut bricks = Vec::new();
+    let Some(first) = candles.first().filter(|_| box_size > 0.0) else {
+        return bricks;
+    };
+
+    // the last brick's edges
+    let (mut top, mut bottom) = (first.close, first.close);
+    for candle in candles {
+        let room = MAX_BOXES.saturating_sub(bricks.len());
+        if room == 0 {
+            break;
+        }
+        let mut made = Vec::new();
+        while candle.close >= top + box_size && made.len() < room {
+            made.push((top, top + box_size));
+            (bottom, top) = (top, top + box_size);
+        }
+        while candle.close <= bottom - box_size && made.len() < room {
+            made.push((bottom, bottom - box_size));
+            (top, bottom) = (bottom, bottom - box_size);
+        }
+
+        let count = made.len() as f64;
+        for (index, (open, close)) in made.into_iter().enumerate() {
+            bricks.push(Candle {
+                x: candle.x - slot / 2.0 + slot * (index as f64 + 0.5) / count,
+                open,
+                close,
+                high: open.max(close),
+                low: open.min(close),
+                volume: candle.volume / count,
+                ..*candle
+            });
+        }
+    }
+    bricks
+}
+
+/// Columns of rising (X) and falling (O) boxes, a new one when the close turns around by
+/// `reversal` boxes. Each runs from its first box (open) to its last (close) and sits at the
+/// bar that started it. Stops once the columns hold [`MAX_BOXES`] boxes.
+pub fn point_and_figure(candles: &[Candle], box_size: f64, reversal: u32) -> Vec<Candle> {
+    let mut columns: Vec<Candle> = Vec::new();
+    let Some(first) = candles.first().filter(|_| box_size > 0.0) else {
+        return columns;
+    };
+
+    let floor = |price: f64| (price / box_size).floor() * box_size;
+    let ceil = |price: f64| (price / box_size).ceil() * box_size;
+    let turn = reversal.max(1) as f64 * box_size;
+    // the last box filled, and which way the current column goes
+    let mut extreme = floor(first.close);
+    let mut rising: Option<bool> = None;
+    // in the columns before the last
+    let mut boxes = 0;
+    for candle in candles {
+        if boxes + columns.last().map_or(0, |column| column_boxes(column, box_size)) >= MAX_BOXES {
+            break;
+        }
+        let price = candle.close;
+        let start = match rising {
+            None if price >= extreme + box_size => Some((true, extreme, floor(price))),
+            None if price <= extreme - box_size => Some((false, extreme, ceil(price))),
+            Some(true) if price <= extreme - turn => Some((false, extreme - box_size, ceil(price))),
+            Some(false) if price >= extreme + turn => Some((true, extreme + box_size, floor(price))),
+            _ => None,
+        };
+
+        if let Some((up, open, close)) = start {
+            boxes += columns.last().map_or(0, |column| column_boxes(column, box_size));
+            rising = Some(up);
+            extreme = close;
+            columns.push(Candle {
+                open,
+                close,
+                high: open.max(close),
+                low: open.min(close),
+                ..*candle
+            });
+            continue;
+        }
+
+        let Some(column) = columns.last_mut() else {
+            continue;
+        };
+        column.volume += candle.volume;
+        match rising {
+            Some(true) if price >= extreme + box_size => extreme = floor(price),
+            Some(false) if price <= extreme - box_size => extreme = ceil(price),
+            _ => continue,
+        }
+        column.close = extreme;
+        column.high = column.high.max(extreme);
+        column.low = column.low.min(extreme);
+    }
+    columns
+}
+
+/// How many boxes a point and figure column is tall.
+pub fn column_boxes(column: &Candle, box_size: f64) -> usize {
+    ((column.high - column.low) / box_size).round() as usize + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn candle(index: usize, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        let time = Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap() + chrono::Duration::minutes(index as i64);
+        Candle { x: time.timestamp_millis() as f64, time, open, high, low, close, volume: 100.0 }
+    }
+
+    fn closing_at(closes: &[f64]) -> Vec<Candle> {
+        closes.iter().enumerate().map(|(index, &close)| candle(index, close, close, close, close)).collect()
+    }
+
+    #[test]
+    fn heikin_ashi_averages() {
+        let candles = [candle(0, 10.0, 12.0, 9.0, 11.0), candle(1, 11.0, 14.0, 10.0, 13.0)];
+        let smoothed = heikin_ashi(&candles);
+        assert_eq!((smoothed[0].open, smoothed[0].close), (10.5, 10.5));
+        assert_eq!((smoothed[1].open, smoothed[1].close), (10.5, 12.0));
+        assert_eq!((smoothed[1].high, smoothed[1].low), (14.0, 10.0));
+        assert_eq!(smoothed[1].x, candles[1].x);
+    }
+
+    #[test]
+    fn renko_needs_two_boxes_to_turn() {
+        let bricks = renko(&closing_at(&[100.0, 103.2, 102.0, 101.0, 98.9]), 1.0, 60_000.0);
+        let edges: Vec<(f64, f64)> = bricks.iter().map(|brick| (brick.open, brick.close)).collect();
+        // up three, then down only once the close is two boxes under the top
+        assert_eq!(edges, [(100.0, 101.0), (101.0, 102.0), (102.0, 103.0), (102.0, 101.0), (101.0, 100.0), (100.0, 99.0)]);
+        // the three bricks of the second bar share its minute and its volume
+        assert!(bricks[0].x < bricks[1].x && bricks[1].x < bricks[2].x);
+        assert!((bricks[0].volume - 100.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn point_and_figure_reverses_after_three_boxes() {
+        let columns = point_and_figure(&closing_at(&[100.0, 105.0, 103.0, 101.5, 104.0, 106.0]), 1.0, 3);
+        let spans: Vec<(f64, f64)> = columns.iter().map(|column| (column.open, column.close)).collect();
+        // 103 isn't a reversal from 105, 101.5 is, and 104 is from 102
+        assert_eq!(spans, [(100.0, 105.0), (104.0, 102.0), (103.0, 106.0)]);
+        assert_eq!(column_boxes(&columns[0], 1.0), 6);
+    }
+
+    #[test]
+    fn a_tiny_box_stops_at_the_cap() {
+        let candles = closing_at(&[1.0, 1000.0, 1.0]);
+        assert_eq!(renko(&candles, 0.01, 60_000.0).len(), MAX_BOXES);
+        let columns = point_and_figure(&candles, 0.01, 3);
+        assert_eq!(columns.len(), 1);
+        assert!(renko(&candles, 0.0, 60_000.0).is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+use rusty_trading_model::structs::{TimeRange, TimeSeries, Transaction};
+
+use crate::{backend::{BackendProfile, encode_query}, orders::{OrderSpec, Side}, price::Price};
+
+/// Called once a backend request has finished, possibly on another thread.
+pub type Callback<T> = Box<dyn FnOnce(Result<T, ClientError>) + Send>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientError {
+    /// The request never got a response (server down, DNS, CORS, ...).
+    Network(String),
+    /// A response arrived, but later than the profile timeout allows.
+    Timeout { elapsed_secs: f32 },
+    /// The server answered with a non-2xx status.
+    Http { status: u16, message: String },
+    /// The request body could not be serialized.
+    Encode(String),
+    /// The response body was not what we expected.
+    Decode(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Network(message) => write!(f, "network error: {message}"),
+            ClientError::Timeout { elapsed_secs } => write!(f, "request timed out after {elapsed_secs:.1}s"),
+            ClientError::Http { status, message } => write!(f, "HTTP {status}: {message}"),
+            ClientError::Encode(message) => write!(f, "could not encode request: {message}"),
+            ClientError::Decode(message) => write!(f, "could not decode response: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// An order as the UI submits it. Goes over the wire as the model's `Transaction` (priced at
+/// the expected fill price) plus our `client_order_id`, so the server can tell resubmits
+/// apart, and the order type fields.
+#[derive(Clone, Debug)]
+pub struct OrderRequest {
+    pub client_order_id: String,
+    pub spec: OrderSpec,
+}
+
+impl OrderRequest {
+    pub fn transaction(&self) -> Transaction {
+        let spec = &self.spec;
+        match spec.side {
+            Side::Buy => Transaction::buy(spec.symbol.clone(), spec.price.to_f64(), spec.qty),
+            Side::Sell => Transaction::sell(spec.symbol.clone(), spec.price.to_f64(), spec.qty),
+        }
+    }
+
+    fn to_json(&self) -> Result<serde_json::Value, ClientError> {
+        let encode = |e: serde_json::Error| ClientError::Encode(e.to_string());
+        let mut value = serde_json::to_value(self.transaction()).map_err(encode)?;
+        if let Some(object) = value.as_object_mut() {
+            object.insert("client_order_id".to_owned(), self.client_order_id.clone().into());
+            object.insert("order_type".to_owned(), serde_json::to_value(self.spec.order_type).map_err(encode)?);
+            object.insert("time_in_force".to_owned(), serde_json::to_value(self.spec.time_in_force).map_err(encode)?);
+            object.insert("limit_price".to_owned(), self.spec.limit_price.map(Price::to_f64).into());
+            object.insert("stop_price".to_owned(), self.spec.stop_price.map(Price::to_f64).into());
+        }
+        Ok(value)
+    }
+}
+
+/// What the server tells us about an order. Everything is optional: older servers answer
+/// with plain text, which we read as "accepted, no id".
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct OrderAck {
+    pub id: Option<String>,
+    /// `accepted`, `rejected`, `partially_filled`, `filled` or `cancelled`.
+    pub status: Option<String>,
+    pub filled_qty: Option<u32>,
+    pub avg_price: Option<f64>,
+    pub reason: Option<String>,
+}
+
+impl OrderAck {
+    fn from_body(bytes: &[u8]) -> Self {
+        serde_json::from_slice(bytes).unwrap_or_default()
+    }
+}
+
+/// Everything the UI needs from the model server.
+pub trait TradingBackend: Send + Sync {
+    /// Cheap liveness probe, used to drive the connection status.
+    fn health_check(&self, on_done: Callback<()>);
+
+    /// With `since`, only points at or after that time are returned, so the latest
+    /// (possibly still forming) bar is always included.
+    fn fetch_time_series(&self, symbol: &str, since: Option<DateTime<Utc>>, on_done: Callback<TimeSeries>);
+
+    fn start_simulation(&self, symbol: &str, on_done: Callback<()>);
+
+    fn pause_simulation(&self, symbol: &str, on_done: Callback<()>);
+
+    fn stop_simulation(&self, symbol: &str, on_done: Callback<()>);
+
+    fn submit_order(&self, order: &OrderRequest, on_done: Callback<OrderAck>);
+
+    /// `order_id` is the server's id from the [`OrderAck`].
+    fn cancel_order(&self, order_id: &str, on_done: Callback<OrderAck>);
+
+    fn fetch_order(&self, order_id: &str, on_done: Callback<OrderAck>);
+
+    /// Looks an order up by the `client_order_id` we sent it with, for orders whose submit
+    /// answer never arrived. A 404 means the server never got it.
+    fn find_order(&self, client_order_id: &str, on_done: Callback<OrderAck>);
+
+    /// Replaces a working order with `order`, same client id, new prices.
+    fn replace_order(&self, order_id: &str, order: &OrderRequest, on_done: Callback<OrderAck>);
+}
+
+/// The real backend, talking to the model server over ehttp.
+pub struct HttpBackend {
+    profile: BackendProfile,
+}
+
+impl HttpBackend {
+    pub fn new(profile: BackendProfile) -> Self {
+        Self { profile }
+    }
+
+    /// Sends the request and checks the status; a response arriving after the profile timeout
+    /// is reported as an error. ehttp has no way to abort a request, so this is the best we can
+    /// do on both native and web.
+    fn send(&self, request: ehttp::Request, on_done: impl 'static + Send + FnOnce(Result<ehttp::Response, ClientError>)) {
+        let timeout = self.profile.timeout();
+        let sent_at = Utc::now();
+        ehttp::fetch(request, move |result| {
+            let elapsed = (Utc::now() - sent_at).to_std().unwrap_or_default();
+            let result = if elapsed > timeout {
+                Err(ClientError::Timeout { elapsed_secs: elapsed.as_secs_f32() })
+            } else {
+                match result {
+                    Ok(response) if response.ok => Ok(response),
+                    Ok(response) => Err(ClientError::Http {
+                        status: response.status,
+                        message: response.text().filter(|text| !text.is_empty()).unwrap_or(&response.status_text).to_owned(),
+                    }),
+                    Err(message) => Err(ClientError::Network(message)),
+                }
+            };
+            on_done(result);
+        });
+    }
+
+    fn simulation_request(&self, endpoint: &str, symbol: &str, on_done: Callback<()>) {
+        let request = match self.profile.post_json(&format!("{endpoint}?stock={}", encode_query(symbol)), &"") {
+            Ok(request) => request,
+            Err(e) => return on_done(Err(ClientError::Encode(e.to_string()))),
+        };
+        self.send(request, move |result| on_done(result.map(|_| ())));
+    }
+}
+
+impl TradingBackend for HttpBackend {
+    fn health_check(&self, on_done: Callback<()>) {
+        let request = self.profile.get(&self.profile.health_path);
+        self.send(request, move |result| on_done(result.map(|_| ())));
+    }
+
+    fn fetch_time_series(&self, symbol: &str, since: Option<DateTime<Utc>>, on_done: Callback<TimeSeries>) {
+        let path = match since {
+            Some(since) => format!("stock?stock={}&since={}", encode_query(symbol), since.timestamp_millis()),
+            None => format!("stock?stock={}", encode_query(symbol)),
+        };
+        let request = self.profile.get(&path);
+        self.send(request, move |result| {
+            on_done(result.and_then(|response| decode(&response.bytes)));
+        });
+    }
+
+    fn start_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.simulation_request("simulation_start", symbol, on_done);
+    }
+
+    fn pause_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.simulation_request("simulation_pause", symbol, on_done);
+    }
+
+    fn stop_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.simulation_request("simulation_stop", symbol, on_done);
+    }
+
+    fn submit_order(&self, order: &OrderRequest, on_done: Callback<OrderAck>) {
+        let request = match order.to_json().and_then(|body| {
+            self.profile.post_json("transaction", &body).map_err(|e| ClientError::Encode(e.to_string()))
+        }) {
+            Ok(request) => request,
+            Err(e) => return on_done(Err(e)),
+        };
+        self.send(request, move |result| {
+            on_done(result.map(|response| OrderAck::from_body(&response.bytes)));
+        });
+    }
+
+    fn cancel_order(&self, order_id: &str, on_done: Callback<OrderAck>) {
+        let request = match self.profile.post_json(&format!("order_cancel?id={}", encode_query(order_id)), &"") {
+            Ok(request) => request,
+            Err(e) => return on_done(Err(ClientError::Encode(e.to_string()))),
+        };
+        self.send(request, move |result| {
+            on_done(result.map(|response| OrderAck::from_body(&response.bytes)));
+        });
+    }
+
+    fn fetch_order(&self, order_id: &str, on_done: Callback<OrderAck>) {
+        let request = self.profile.get(&format!("order?id={}", encode_query(order_id)));
+        self.send(request, move |result| on_done(result.and_then(|response| decode(&response.bytes))));
+    }
+
+    fn find_order(&self, client_order_id: &str, on_done: Callback<OrderAck>) {
+        let request = self.profile.get(&format!("order?client_order_id={}", encode_query(client_order_id)));
+        self.send(request, move |result| on_done(result.and_then(|response| decode(&response.bytes))));
+    }
+
+    fn replace_order(&self, order_id: &str, order: &OrderRequest, on_done: Callback<OrderAck>) {
+        let request = match order.to_json().and_then(|body| {
+            self.profile
+                .post_json(&format!("order_replace?id={}", encode_query(order_id)), &body)
+                .map_err(|e| ClientError::Encode(e.to_string()))
+        }) {
+            Ok(request) => request,
+            Err(e) => return on_done(Err(e)),
+        };
+        self.send(request, move |result| {
+            on_done(result.map(|response| OrderAck::from_body(&response.bytes)));
+        });
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, ClientError> {
+    serde_json::from_slice(bytes).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// In-memory stand-in for the model server, for running the UI offline or against canned data.
+/// Requests complete synchronously.
+#[derive(Default)]
+pub struct MockBackend {
+    // stored as JSON so responses go through the same decoding as the real thing
+    time_series: Mutex<HashMap<String, Vec<u8>>>,
+    simulations: Mutex<Vec<(String, &'static str)>>,
+    transactions: Mutex<Vec<serde_json::Value>>,
+    // by id, market and limit orders fill the first time they're polled unless fills are
+    // manual, stops never trigger
+    orders: Mutex<HashMap<String, MockOrder>>,
+    manual_fills: Mutex<bool>,
+    failure: Mutex<Option<ClientError>>,
+}
+
+struct MockOrder {
+    request: OrderRequest,
+    status: &'static str,
+    filled_qty: u32,
+}
+
+impl MockOrder {
+    fn is_working(&self) -> bool {
+        matches!(self.status, "accepted" | "partially_filled")
+    }
+}
+
+impl MockBackend {
+    pub fn set_time_series(&self, symbol: &str, time_series: &TimeSeries) {
+        let bytes = serde_json::to_vec(time_series).unwrap_or_default();
+        self.time_series.lock().unwrap().insert(symbol.to_owned(), bytes);
+    }
+
+    /// Makes every following request fail with `error`, or succeed again with `None`.
+    pub fn fail_with(&self, error: Option<ClientError>) {
+        *self.failure.lock().unwrap() = error;
+    }
+
+    /// Simulation requests so far as `(symbol, action)`, in order.
+    pub fn simulations(&self) -> Vec<(String, &'static str)> {
+        self.simulations.lock().unwrap().clone()
+    }
+
+    /// Transactions submitted so far, as they would have gone over the wire.
+    pub fn transactions(&self) -> Vec<serde_json::Value> {
+        self.transactions.lock().unwrap().clone()
+    }
+
+    /// Stops polls from filling orders, only [`MockBackend::fill`] does from then on.
+    pub fn fill_manually(&self) {
+        *self.manual_fills.lock().unwrap() = true;
+    }
+
+    /// Fills `qty` more of the working order sent as `client_order_id`, at its price. The
+    /// next poll reports it.
+    pub fn fill(&self, client_order_id: &str, qty: u32) {
+        let mut orders = self.orders.lock().unwrap();
+        let Some(order) = orders.values_mut().find(|order| order.request.client_order_id == client_order_id && order.is_working()) else {
+            return;
+        };
+        order.filled_qty = (order.filled_qty + qty).min(order.request.spec.qty);
+        order.status = if order.filled_qty == order.request.spec.qty { "filled" } else { "partially_filled" };
+    }
+
+    fn order_ack(&self, order_id: &str, update: impl FnOnce(&mut MockOrder)) -> Result<OrderAck, ClientError> {
+        let mut orders = self.orders.lock().unwrap();
+        let Some(order) = orders.get_mut(order_id) else {
+            return Err(ClientError::Http { status: 404, message: format!("no order {order_id}") });
+        };
+        update(order);
+        Ok(OrderAck {
+            id: Some(order_id.to_owned()),
+            status: Some(order.status.to_owned()),
+            filled_qty: Some(order.filled_qty),
+            avg_price: (order.filled_qty > 0).then(|| order.request.spec.price.to_f64()),
+            reason: None,
+        })
+    }
+
+    fn check_failure(&self) -> Result<(), ClientError> {
+        match self.failure.lock().unwrap().clone() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn record_simulation(&self, symbol: &str, action: &'static str, on_done: Callback<()>) {
+        let result = self.check_failure().map(|_| self.simulations.lock().unwrap().push((symbol.to_owned(), action)));
+        on_done(result);
+    }
+}
+
+impl TradingBackend for MockBackend {
+    fn health_check(&self, on_done: Callback<()>) {
+        on_done(self.check_failure());
+    }
+
+    fn fetch_time_series(&self, symbol: &str, since: Option<DateTime<Utc>>, on_done: Callback<TimeSeries>) {
+        let result = self.check_failure().and_then(|_| {
+            let map = self.time_series.lock().unwrap();
+            let Some(bytes) = map.get(symbol) else {
+                return Ok(TimeSeries::new(TimeRange::Day, Utc::now(), Utc::now(), vec![]));
+            };
+            let mut time_series: TimeSeries = decode(bytes)?;
+            let Some(since) = since else {
+                return Ok(time_series);
+            };
+            let points: Vec<_> = time_series.data().iter().filter(|point| point.timestamp >= since).cloned().collect();
+            let end = points.last().map_or(since, |point| point.timestamp);
+            Ok(TimeSeries::new(TimeRange::Day, since, end, points))
+        });
+        on_done(result);
+    }
+
+    fn start_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.record_simulation(symbol, "start", on_done);
+    }
+
+    fn pause_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.record_simulation(symbol, "pause", on_done);
+    }
+
+    fn stop_simulation(&self, symbol: &str, on_done: Callback<()>) {
+        self.record_simulation(symbol, "stop", on_done);
+    }
+
+    fn submit_order(&self, order: &OrderRequest, on_done: Callback<OrderAck>) {
+        let result = self.check_failure().and_then(|_| {
+            self.transactions.lock().unwrap().push(order.to_json()?);
+            let mut orders = self.orders.lock().unwrap();
+            let id = format!("mock-{}", orders.len() + 1);
+            orders.insert(id.clone(), MockOrder { request: order.clone(), status: "accepted", filled_qty: 0 });
+            Ok(OrderAck { id: Some(id), status: Some("accepted".to_owned()), ..Default::default() })
+        });
+        on_done(result);
+    }
+
+    fn cancel_order(&self, order_id: &str, on_done: Callback<OrderAck>) {
+        let result = self.check_failure().and_then(|_| {
+            self.order_ack(order_id, |order| {
+                if order.is_working() {
+                    order.status = "cancelled";
+                }
+            })
+        });
+        on_done(result);
+    }
+
+    fn fetch_order(&self, order_id: &str, on_done: Callback<OrderAck>) {
+        let manual = *self.manual_fills.lock().unwrap();
+        let result = self.check_failure().and_then(|_| {
+            self.order_ack(order_id, |order| {
+                if order.status == "accepted" && !manual && !order.request.spec.order_type.has_stop() {
+                    order.status = "filled";
+                    order.filled_qty = order.request.spec.qty;
+                }
+            })
+        });
+        on_done(result);
+    }
+
+    fn find_order(&self, client_order_id: &str, on_done: Callback<OrderAck>) {
+        let result = self.check_failure().and_then(|_| {
+            let order_id = self
+                .orders
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(_, order)| order.request.client_order_id == client_order_id)
+                .map(|(order_id, _)| order_id.clone());
+            match order_id {
+                Some(order_id) => self.order_ack(&order_id, |_| {}),
+                None => Err(ClientError::Http { status: 404, message: format!("no order {client_order_id}") }),
+            }
+        });
+        on_done(result);
+    }
+
+    fn replace_order(&self, order_id: &str, order: &OrderRequest, on_done: Callback<OrderAck>) {
+        let result = self.check_failure().and_then(|_| {
+            if let Some(current) = self.orders.lock().unwrap().get_mut(order_id) {
+                // too late once it's filled or cancelled, the ack says which
+                if current.is_working() {
+                    current.request = order.clone();
+                }
+            }
+            self.order_ack(order_id, |_| {})
+        });
+        on_done(result);
+    }
+}
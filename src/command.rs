@@ -0,0 +1,183 @@
+use crate::{
+    orders::{OrderType, Side, TimeInForce},
+    price::Price,
+    ticket::OrderTicket,
+};
+
+/// Examples shown in the order entry box.
+pub const EXAMPLES: [&str; 4] = [
+    "buy 100 AAPL @ 175.5 lmt",
+    "sell 50 MSFT mkt ioc",
+    "sell 100 AAPL stp 170",
+    "buy 10 TSLA @ 251 stp 250 gtc",
+];
+
+/// An order typed into the order entry box, e.g. `buy 100 AAPL @ 175.5 lmt`.
+///
+/// Side first, the rest in any order: the quantity, the symbol, `@ price` for a limit,
+/// `stp price` for a stop, and optionally the order type (`mkt`, `lmt`, `stp`, `stplmt`) and
+/// time in force (`day`, `gtc`, `ioc`, `fok`). Without a type it follows from the prices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderCommand {
+    pub side: Side,
+    pub qty: u32,
+    pub symbol: String,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub limit_price: Option<Price>,
+    pub stop_price: Option<Price>,
+}
+
+impl OrderCommand {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = text.split_whitespace().peekable();
+        let side = match tokens.next().map(str::to_lowercase).as_deref() {
+            Some("buy" | "b") => Side::Buy,
+            Some("sell" | "s") => Side::Sell,
+            Some(other) => return Err(format!("Start with buy or sell, not '{other}'")),
+            None => return Err("Type an order, e.g. buy 100 AAPL @ 175.5".to_owned()),
+        };
+
+        let mut qty = None;
+        let mut symbol = None;
+        let mut order_type = None;
+        let mut time_in_force = None;
+        let mut limit_price = None;
+        let mut stop_price = None;
+        while let Some(token) = tokens.next() {
+            let lower = token.to_lowercase();
+            match lower.as_str() {
+                "mkt" | "market" => order_type = Some(OrderType::Market),
+                "lmt" | "limit" => order_type = Some(OrderType::Limit),
+                "stplmt" | "stop-limit" => order_type = Some(OrderType::StopLimit),
+                "day" => time_in_force = Some(TimeInForce::Day),
+                "gtc" => time_in_force = Some(TimeInForce::Gtc),
+                "ioc" => time_in_force = Some(TimeInForce::Ioc),
+                "fok" => time_in_force = Some(TimeInForce::Fok),
+                "stp" | "stop" => {
+                    // a price right after it is the stop price, otherwise it's just the type
+                    match tokens.peek().map(|next| Price::parse(next)) {
+                        Some(Ok(price)) => {
+                            stop_price = Some(price);
+                            tokens.next();
+                        }
+                        _ => order_type = Some(OrderType::Stop),
+                    }
+                }
+                "@" => {
+                    let price = tokens.next().ok_or("'@' needs a price after it")?;
+                    limit_price = Some(Price::parse(price)?);
+                }
+                _ if lower.starts_with('@') => limit_price = Some(Price::parse(&lower[1..])?),
+                _ if qty.is_none() && token.chars().all(|c| c.is_ascii_digit()) => {
+                    qty = Some(token.parse::<u32>().map_err(|_| format!("'{token}' is too many shares"))?);
+                }
+                _ if symbol.is_none() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') => {
+                    symbol = Some(token.to_uppercase());
+                }
+                _ => return Err(format!("Don't know what '{token}' means")),
+            }
+        }
+
+        let qty = qty.ok_or("Missing the quantity")?;
+        let symbol = symbol.ok_or("Missing the symbol")?;
+        let order_type = order_type.unwrap_or(match (limit_price, stop_price) {
+            (None, None) => OrderType::Market,
+            (Some(_), None) => OrderType::Limit,
+            (None, Some(_)) => OrderType::Stop,
+            (Some(_), Some(_)) => OrderType::StopLimit,
+        });
+        if order_type.has_limit() != limit_price.is_some() {
+            return Err(match limit_price {
+                Some(_) => format!("{} orders don't take '@ price'", order_type.label()),
+                None => format!("{} orders need '@ price'", order_type.label()),
+            });
+        }
+        if order_type.has_stop() != stop_price.is_some() {
+            return Err(match stop_price {
+                Some(_) => format!("{} orders don't take 'stp price'", order_type.label()),
+                None => format!("{} orders need 'stp price'", order_type.label()),
+            });
+        }
+
+        Ok(Self {
+            side,
+            qty,
+            symbol,
+            order_type,
+            time_in_force: time_in_force.unwrap_or_default(),
+            limit_price,
+            stop_price,
+        })
+    }
+
+    /// One line for the preview, e.g. `BUY 100 AAPL, Limit DAY, limit $175.50`.
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} {} {}, {} {}", self.side, self.qty, self.symbol, self.order_type.label(), self.time_in_force.label());
+        if let Some(stop) = self.stop_price {
+            text += &format!(", stop ${stop}");
+        }
+        if let Some(limit) = self.limit_price {
+            text += &format!(", limit ${limit}");
+        }
+        text
+    }
+
+    /// Puts the order into a ticket's form, which then checks it like a typed-in one.
+    pub fn fill(&self, ticket: &mut OrderTicket) {
+        ticket.qty = self.qty.to_string();
+        ticket.order_type = self.order_type;
+        ticket.time_in_force = self.time_in_force;
+        ticket.price = self.limit_price.map(|price| price.to_string()).unwrap_or_default();
+        ticket.stop_price = self.stop_price.map(|price| price.to_string()).unwrap_or_default();
+        ticket.bracket = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_examples() {
+        for example in EXAMPLES {
+            assert!(OrderCommand::parse(example).is_ok(), "{example}");
+        }
+    }
+
+    #[test]
+    fn parses_a_limit_order() {
+        let command = OrderCommand::parse("buy 100 aapl @ 175.5 lmt").unwrap();
+        assert_eq!(
+            command,
+            OrderCommand {
+                side: Side::Buy,
+                qty: 100,
+                symbol: "AAPL".to_owned(),
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::Day,
+                limit_price: Some(Price::from_cents(17550)),
+                stop_price: None,
+            }
+        );
+    }
+
+    #[test]
+    fn works_out_the_type_from_the_prices() {
+        let parse = |text| OrderCommand::parse(text).unwrap();
+        assert_eq!(parse("s 50 MSFT").order_type, OrderType::Market);
+        assert_eq!(parse("b 5 MSFT @300").order_type, OrderType::Limit);
+        assert_eq!(parse("sell 100 AAPL stp 170").order_type, OrderType::Stop);
+        let stop_limit = parse("buy 10 TSLA @ 251 stp 250 gtc");
+        assert_eq!(stop_limit.order_type, OrderType::StopLimit);
+        assert_eq!(stop_limit.time_in_force, TimeInForce::Gtc);
+        assert_eq!(stop_limit.stop_price, Some(Price::from_cents(25000)));
+    }
+
+    #[test]
+    fn says_what_is_wrong() {
+        for text in ["", "hold 100 AAPL", "buy AAPL", "buy 100", "buy 100 AAPL @", "buy 100 AAPL lmt", "buy 100 AAPL mkt @ 5", "buy 100 AAPL ???"] {
+            assert!(OrderCommand::parse(text).is_err(), "{text:?} parsed");
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{sync::{Arc, Mutex}, time::Duration};
+
+use chrono::{DateTime, Utc};
+use egui::Color32;
+
+use crate::client::{ClientError, TradingBackend};
+
+/// How often we probe the backend while it is reachable.
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+/// Market data polling interval while the backend is healthy.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Upper bound for the backoff while disconnected.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Probes slower than this mark the connection as degraded.
+const SLOW_PROBE: Duration = Duration::from_millis(1000);
+/// Consecutive failed probes before we give up and call it disconnected.
+const FAILURES_BEFORE_DISCONNECT: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No probe has completed yet.
+    Connecting,
+    Connected,
+    /// Reachable but slow, or failing intermittently.
+    Degraded,
+    Disconnected,
+}
+
+impl ConnectionState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionState::Connecting => "Connecting",
+            ConnectionState::Connected => "Connected",
+            ConnectionState::Degraded => "Degraded",
+            ConnectionState::Disconnected => "Disconnected",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            ConnectionState::Connecting => Color32::from_rgb(150, 150, 150),
+            ConnectionState::Connected => Color32::from_rgb(0, 255, 0),
+            ConnectionState::Degraded => Color32::from_rgb(255, 165, 0),
+            ConnectionState::Disconnected => Color32::from_rgb(255, 0, 0),
+        }
+    }
+}
+
+/// Tracks backend health from periodic probes. Shared with the probe callbacks, so it lives
+/// behind an `Arc<Mutex<_>>` in the app.
+pub struct ConnectionMonitor {
+    state: ConnectionState,
+    last_error: Option<String>,
+    latency: Option<Duration>,
+    consecutive_failures: u32,
+    last_probe: Option<DateTime<Utc>>,
+    probe_in_flight: bool,
+}
+
+impl Default for ConnectionMonitor {
+    fn default() -> Self {
+        Self {
+            state: ConnectionState::Connecting,
+            last_error: None,
+            latency: None,
+            consecutive_failures: 0,
+            last_probe: None,
+            probe_in_flight: false,
+        }
+    }
+}
+
+impl ConnectionMonitor {
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Forget everything we know, e.g. after the backend profile changed.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Doubles with every consecutive failure while disconnected, capped at `MAX_BACKOFF`.
+    fn backoff(&self, base: Duration) -> Duration {
+        if self.state != ConnectionState::Disconnected {
+            return base;
+        }
+        let exponent = self.consecutive_failures.saturating_sub(FAILURES_BEFORE_DISCONNECT).min(5);
+        (base * 2u32.pow(exponent)).min(MAX_BACKOFF)
+    }
+
+    /// How long to wait between market data polls.
+    pub fn poll_interval(&self) -> Duration {
+        self.backoff(POLL_INTERVAL)
+    }
+
+    fn probe_interval(&self) -> Duration {
+        match self.state {
+            ConnectionState::Connected => PROBE_INTERVAL,
+            _ => self.backoff(POLL_INTERVAL),
+        }
+    }
+
+    fn probe_due(&self, now: DateTime<Utc>) -> bool {
+        !self.probe_in_flight
+            && self.last_probe.is_none_or(|last| last + self.probe_interval() <= now)
+    }
+
+    fn record_success(&mut self, latency: Duration) {
+        self.consecutive_failures = 0;
+        self.latency = Some(latency);
+        self.last_error = None;
+        self.state = if latency > SLOW_PROBE {
+            ConnectionState::Degraded
+        } else {
+            ConnectionState::Connected
+        };
+    }
+
+    fn record_failure(&mut self, error: &ClientError) {
+        self.consecutive_failures += 1;
+        self.latency = None;
+        self.last_error = Some(error.to_string());
+        self.state = match self.state {
+            ConnectionState::Connected | ConnectionState::Degraded
+                if self.consecutive_failures < FAILURES_BEFORE_DISCONNECT =>
+            {
+                ConnectionState::Degraded
+            }
+            _ => ConnectionState::Disconnected,
+        };
+    }
+}
+
+/// Fires a health probe if one is due. The monitor is updated when the probe completes.
+pub fn probe_if_due(monitor: &Arc<Mutex<ConnectionMonitor>>, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    let now = Utc::now();
+    {
+        let mut guard = monitor.lock().unwrap();
+        if !guard.probe_due(now) {
+            return;
+        }
+        guard.probe_in_flight = true;
+        guard.last_probe = Some(now);
+    }
+
+    let monitor = Arc::clone(monitor);
+    let ctx = ctx.clone();
+    backend.health_check(Box::new(move |result| {
+        let mut guard = monitor.lock().unwrap();
+        guard.probe_in_flight = false;
+        match result {
+            Ok(()) => guard.record_success((Utc::now() - now).to_std().unwrap_or_default()),
+            Err(e) => {
+                log::warn!("Backend health check failed: {e}");
+                guard.record_failure(&e);
+            }
+        }
+        ctx.request_repaint();
+    }));
+}
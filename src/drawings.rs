@@ -0,0 +1,309 @@
+use egui::{Align2, Color32, Pos2, RichText, Stroke};
+use egui_plot::{HLine, Line, LineStyle, MarkerShape, PlotPoint, PlotUi, Points, Polygon, Text};
+
+use crate::scale::YScale;
+
+// how close the pointer has to be to a handle to grab it
+const GRAB_DISTANCE: f32 = 6.0;
+
+/// Retracement levels drawn between a Fibonacci drawing's two points.
+pub const FIBONACCI_LEVELS: [f64; 7] = [0.0, 0.236, 0.382, 0.5, 0.618, 0.786, 1.0];
+
+/// What a new drawing will be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    HorizontalLine,
+    TrendLine,
+    Rectangle,
+    Fibonacci,
+    Note,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 5] = [Tool::HorizontalLine, Tool::TrendLine, Tool::Rectangle, Tool::Fibonacci, Tool::Note];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tool::HorizontalLine => "― Line",
+            Tool::TrendLine => "╱ Trend",
+            Tool::Rectangle => "▭ Box",
+            Tool::Fibonacci => "☰ Fib",
+            Tool::Note => "🗒 Note",
+        }
+    }
+
+    /// Dragged out from one point to another, the others are placed with a click.
+    pub fn is_dragged(&self) -> bool {
+        matches!(self, Tool::TrendLine | Tool::Rectangle | Tool::Fibonacci)
+    }
+}
+
+/// Where a drawing is: x is the time in milliseconds, y the price, whatever the price axis shows.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Shape {
+    HorizontalLine { price: f64 },
+    TrendLine { from: [f64; 2], to: [f64; 2] },
+    Rectangle { from: [f64; 2], to: [f64; 2] },
+    Fibonacci { from: [f64; 2], to: [f64; 2] },
+    Note { at: [f64; 2], text: String },
+}
+
+/// Something drawn on a chart by hand.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Drawing {
+    pub shape: Shape,
+    pub color: Color32,
+}
+
+impl Drawing {
+    pub const DEFAULT_COLOR: Color32 = Color32::from_rgb(80, 170, 255);
+
+    /// A new drawing from `from` to `to`; the tools placed with a click only use `from`.
+    pub fn new(tool: Tool, from: [f64; 2], to: [f64; 2]) -> Self {
+        let shape = match tool {
+            Tool::HorizontalLine => Shape::HorizontalLine { price: from[1] },
+            Tool::TrendLine => Shape::TrendLine { from, to },
+            Tool::Rectangle => Shape::Rectangle { from, to },
+            Tool::Fibonacci => Shape::Fibonacci { from, to },
+            Tool::Note => Shape::Note { at: from, text: "Note".to_owned() },
+        };
+        Self { shape, color: Self::DEFAULT_COLOR }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.shape {
+            Shape::HorizontalLine { .. } => "Line",
+            Shape::TrendLine { .. } => "Trend line",
+            Shape::Rectangle { .. } => "Box",
+            Shape::Fibonacci { .. } => "Fibonacci",
+            Shape::Note { .. } => "Note",
+        }
+    }
+
+    /// The points that can be dragged. A horizontal line has none, it's grabbed anywhere.
+    fn handles(&self) -> Vec<[f64; 2]> {
+        match &self.shape {
+            Shape::HorizontalLine { .. } => Vec::new(),
+            Shape::TrendLine { from, to } | Shape::Rectangle { from, to } | Shape::Fibonacci { from, to } => vec![*from, *to],
+            Shape::Note { at, .. } => vec![*at],
+        }
+    }
+
+    /// The handle within `distance` pixels of the pointer, and how far it is.
+    pub fn handle_at(&self, pointer: Pos2, distance: f32, to_screen: impl Fn([f64; 2]) -> Pos2) -> Option<(usize, f32)> {
+        if let Shape::HorizontalLine { price } = self.shape {
+            let off = (to_screen([0.0, price]).y - pointer.y).abs();
+            return (off <= distance).then_some((0, off));
+        }
+        self.handles()
+            .into_iter()
+            .enumerate()
+            .map(|(handle, point)| (handle, to_screen(point).distance(pointer)))
+            .filter(|&(_, off)| off <= distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    pub fn move_handle(&mut self, handle: usize, point: [f64; 2]) {
+        match &mut self.shape {
+            Shape::HorizontalLine { price } => *price = point[1],
+            Shape::TrendLine { from, to } | Shape::Rectangle { from, to } | Shape::Fibonacci { from, to } => {
+                *(if handle == 0 { from } else { to }) = point;
+            }
+            Shape::Note { at, .. } => *at = point,
+        }
+    }
+
+    /// Draws it, with its handles when it's selected.
+    pub fn draw(&self, plot_ui: &mut PlotUi, selected: bool, scale: YScale) {
+        let color = self.color;
+        let width = if selected { 2.0 } else { 1.5 };
+        let plot = |point: [f64; 2]| [point[0], scale.to_plot(point[1])];
+        // unnamed, they'd crowd the legend
+        match &self.shape {
+            Shape::HorizontalLine { price } => {
+                plot_ui.hline(HLine::new("", scale.to_plot(*price)).color(color).width(width));
+            }
+            Shape::TrendLine { from, to } => {
+                plot_ui.line(Line::new("", vec![plot(*from), plot(*to)]).color(color).width(width));
+            }
+            Shape::Rectangle { from, to } => {
+                let corners = vec![plot(*from), plot([to[0], from[1]]), plot(*to), plot([from[0], to[1]])];
+                plot_ui.polygon(
+                    Polygon::new("", corners)
+                        .stroke(Stroke::new(width, color))
+                        .fill_color(color.gamma_multiply(0.15)),
+                );
+            }
+            Shape::Fibonacci { from, to } => {
+                let (left, right) = (from[0].min(to[0]), from[0].max(to[0]));
+                plot_ui.line(Line::new("", vec![plot(*from), plot(*to)]).color(color).style(LineStyle::dashed_dense()));
+                for level in FIBONACCI_LEVELS {
+                    // measured back from the end of the move, like a retracement
+                    let price = to[1] - (to[1] - from[1]) * level;
+                    let y = scale.to_plot(price);
+                    plot_ui.line(Line::new("", vec![[left, y], [right, y]]).color(color).width(width));
+                    plot_ui.text(
+                        Text::new("", PlotPoint::new(left, y), format!("{:.1}% {price:.2}", level * 100.0))
+                            .color(color)
+                            .anchor(Align2::LEFT_BOTTOM),
+                    );
+                }
+            }
+            Shape::Note { at, text } => {
+                let text = RichText::new(text).color(color).background_color(Color32::from_black_alpha(160));
+                plot_ui.text(Text::new("", PlotPoint::new(at[0], scale.to_plot(at[1])), text).anchor(Align2::LEFT_BOTTOM));
+            }
+        }
+
+        if selected {
+            let handles: Vec<[f64; 2]> = self.handles().into_iter().map(plot).collect();
+            if !handles.is_empty() {
+                plot_ui.points(Points::new("", handles).shape(MarkerShape::Square).radius(4.0).color(color));
+            }
+        }
+    }
+}
+
+/// A chart's drawings, and what's being done to them.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Drawings {
+    drawings: Vec<Drawing>,
+    // picked in the toolbar, dropped again once the drawing is placed
+    #[serde(skip)]
+    tool: Option<Tool>,
+    #[serde(skip)]
+    selected: Option<usize>,
+    // drawing and handle being dragged
+    #[serde(skip)]
+    drag: Option<(usize, usize)>,
+    // where the drawing being dragged out started
+    #[serde(skip)]
+    draft_start: Option<[f64; 2]>,
+}
+
+impl Drawings {
+    /// The pointer is theirs, the plot mustn't pan.
+    pub fn is_busy(&self) -> bool {
+        self.tool.is_some() || self.drag.is_some()
+    }
+
+    pub fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("✏");
+            for tool in Tool::ALL {
+                if ui.selectable_label(self.tool == Some(tool), tool.label()).clicked() {
+                    self.tool = if self.tool == Some(tool) { None } else { Some(tool) };
+                }
+            }
+
+            if let Some(index) = self.selected.filter(|&index| index < self.drawings.len()) {
+                ui.separator();
+                let drawing = &mut self.drawings[index];
+                ui.label(drawing.name());
+                ui.color_edit_button_srgba(&mut drawing.color);
+                if let Shape::Note { text, .. } = &mut drawing.shape {
+                    ui.add(egui::TextEdit::singleline(text).desired_width(120.0));
+                }
+                if ui.small_button("🗑").on_hover_text("Delete (Del)").clicked() {
+                    self.remove(index);
+                }
+            }
+
+            if !self.drawings.is_empty() {
+                ui.separator();
+                if ui.small_button("Clear all").clicked() {
+                    self.drawings.clear();
+                    self.selected = None;
+                    self.drag = None;
+                }
+            }
+        });
+    }
+
+    /// Draws them and handles the pointer on the plot: dragging handles, placing a drawing
+    /// with the picked tool, selecting with a click, Delete and Escape. `blocked` is when
+    /// something else on the plot has the pointer. Returns whether a handle is under it.
+    pub fn interact(&mut self, plot_ui: &mut PlotUi, response: &egui::Response, blocked: bool, scale: YScale) -> bool {
+        let pointer = plot_ui.pointer_coordinate().map(|pointer| [pointer.x, scale.to_price(pointer.y)]);
+        let hovered = pointer.filter(|_| response.hovered() && !blocked).and_then(|pointer| {
+            let to_screen = |point: [f64; 2]| plot_ui.screen_from_plot(PlotPoint::new(point[0], scale.to_plot(point[1])));
+            let pointer = to_screen(pointer);
+            self.drawings
+                .iter()
+                .enumerate()
+                .filter_map(|(index, drawing)| drawing.handle_at(pointer, GRAB_DISTANCE, to_screen).map(|(handle, off)| ((index, handle), off)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(grabbed, _)| grabbed)
+        });
+
+        if response.drag_started_by(egui::PointerButton::Primary) && !blocked {
+            match self.tool.filter(Tool::is_dragged) {
+                Some(_) => self.draft_start = pointer,
+                None => {
+                    self.drag = hovered;
+                    self.selected = hovered.map(|(index, _)| index).or(self.selected);
+                }
+            }
+        }
+        let dragged = self.drag.filter(|&(index, _)| index < self.drawings.len());
+        if let (Some((index, handle)), Some(pointer)) = (dragged, pointer) {
+            self.drawings[index].move_handle(handle, pointer);
+        }
+        if response.drag_stopped() {
+            self.drag = None;
+            // let go outside the plot, nothing to place
+            let draft = self.draft_start.take().zip(self.tool).zip(pointer);
+            if let Some(((start, tool), end)) = draft.filter(|((start, _), end)| start != end) {
+                self.add(Drawing::new(tool, start, end));
+            }
+        }
+        if response.clicked_by(egui::PointerButton::Primary) && !blocked {
+            match (self.tool.filter(|tool| !tool.is_dragged()), pointer) {
+                (Some(tool), Some(pointer)) => self.add(Drawing::new(tool, pointer, pointer)),
+                _ => self.selected = hovered.map(|(index, _)| index),
+            }
+        }
+
+        // not while typing, e.g. a note's text in the toolbar
+        if response.hovered() && !plot_ui.ctx().wants_keyboard_input() {
+            let (delete, escape) = plot_ui.ctx().input(|i| (i.key_pressed(egui::Key::Delete), i.key_pressed(egui::Key::Escape)));
+            if let Some(index) = self.selected.filter(|_| delete) {
+                self.remove(index);
+            }
+            if escape {
+                self.tool = None;
+                self.draft_start = None;
+            }
+        }
+
+        for (index, drawing) in self.drawings.iter().enumerate() {
+            drawing.draw(plot_ui, self.selected == Some(index), scale);
+        }
+        if let (Some(start), Some(tool), Some(end)) = (self.draft_start, self.tool, pointer) {
+            Drawing::new(tool, start, end).draw(plot_ui, false, scale);
+        }
+
+        if self.drag.is_some() {
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+        } else if hovered.is_some() {
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+        } else if self.tool.is_some() && response.hovered() {
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+        }
+        hovered.is_some()
+    }
+
+    fn add(&mut self, drawing: Drawing) {
+        self.drawings.push(drawing);
+        self.selected = Some(self.drawings.len() - 1);
+        self.tool = None;
+    }
+
+    fn remove(&mut self, index: usize) {
+        if index < self.drawings.len() {
+            self.drawings.remove(index);
+        }
+        self.selected = None;
+        self.drag = None;
+    }
+}
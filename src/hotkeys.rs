@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use crate::orders::Side;
+
+/// Something a key binding can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+pub enum Action {
+    ToggleHelp,
+    Quit,
+    OrderEntry,
+    BuyBid,
+    BuyAsk,
+    SellBid,
+    SellAsk,
+    CancelAll,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::ToggleHelp,
+        Action::Quit,
+        Action::OrderEntry,
+        Action::BuyBid,
+        Action::BuyAsk,
+        Action::SellBid,
+        Action::SellAsk,
+        Action::CancelAll,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::ToggleHelp => "Toggle this help window",
+            Action::Quit => "Quit application",
+            Action::OrderEntry => "Type an order",
+            Action::BuyBid => "Buy at the bid",
+            Action::BuyAsk => "Buy at the ask",
+            Action::SellBid => "Sell at the bid",
+            Action::SellAsk => "Sell at the ask",
+            Action::CancelAll => "Cancel all open orders",
+        }
+    }
+
+    /// The side and quote of a trading hotkey, which goes to the stock window in front.
+    pub fn order(&self) -> Option<(Side, Quote)> {
+        match self {
+            Action::BuyBid => Some((Side::Buy, Quote::Bid)),
+            Action::BuyAsk => Some((Side::Buy, Quote::Ask)),
+            Action::SellBid => Some((Side::Sell, Quote::Bid)),
+            Action::SellAsk => Some((Side::Sell, Quote::Ask)),
+            _ => None,
+        }
+    }
+
+    fn default_shortcut(&self) -> Option<KeyboardShortcut> {
+        let shortcut = |modifiers, key| Some(KeyboardShortcut::new(modifiers, key));
+        let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+        match self {
+            Action::ToggleHelp => shortcut(Modifiers::NONE, Key::F1),
+            Action::Quit => shortcut(Modifiers::COMMAND, Key::Q),
+            Action::OrderEntry => shortcut(Modifiers::COMMAND, Key::N),
+            Action::BuyBid => shortcut(Modifiers::COMMAND, Key::B),
+            Action::BuyAsk => shortcut(command_shift, Key::B),
+            Action::SellBid => shortcut(command_shift, Key::S),
+            Action::SellAsk => shortcut(Modifiers::COMMAND, Key::S),
+            Action::CancelAll => shortcut(command_shift, Key::X),
+        }
+    }
+}
+
+/// Which side of the quote a hotkey order is priced at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quote {
+    Bid,
+    Ask,
+}
+
+/// Key bindings, changeable in the settings. Actions missing from `bindings` use their
+/// default, `None` means unbound.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub bindings: BTreeMap<Action, Option<KeyboardShortcut>>,
+    /// Shares for a hotkey order when the stock window's ticket has no quantity.
+    pub order_qty: u32,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self { bindings: BTreeMap::new(), order_qty: 100 }
+    }
+}
+
+impl Hotkeys {
+    pub fn shortcut(&self, action: Action) -> Option<KeyboardShortcut> {
+        self.bindings.get(&action).copied().unwrap_or_else(|| action.default_shortcut())
+    }
+
+    /// Binds `action`, taking the shortcut away from whatever had it before.
+    pub fn bind(&mut self, action: Action, shortcut: Option<KeyboardShortcut>) {
+        if shortcut.is_some() {
+            for other in Action::ALL {
+                if other != action && self.shortcut(other) == shortcut {
+                    self.bindings.insert(other, None);
+                }
+            }
+        }
+        self.bindings.insert(action, shortcut);
+    }
+
+    pub fn reset(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Shortcut text for the help window and settings, e.g. `Ctrl+Shift+B`.
+    pub fn describe(&self, ctx: &egui::Context, action: Action) -> String {
+        match self.shortcut(action) {
+            Some(shortcut) => ctx.format_shortcut(&shortcut),
+            None => "Unbound".to_owned(),
+        }
+    }
+
+    /// The actions whose keys were pressed this frame, consuming the key presses. Plain keys
+    /// are left alone while a text field has the keyboard, they're typing.
+    pub fn pressed(&self, ctx: &egui::Context) -> Vec<Action> {
+        let typing = ctx.wants_keyboard_input();
+        let mut bound: Vec<(Action, KeyboardShortcut)> = Action::ALL
+            .into_iter()
+            .filter_map(|action| self.shortcut(action).map(|shortcut| (action, shortcut)))
+            .filter(|(_, shortcut)| !(typing && types_text(shortcut)))
+            .collect();
+        // extra modifiers still match, so Ctrl+Shift+B has to be tried before Ctrl+B
+        bound.sort_by_key(|(_, shortcut)| std::cmp::Reverse(modifier_count(shortcut.modifiers)));
+        ctx.input_mut(|i| {
+            bound
+                .into_iter()
+                .filter(|(_, shortcut)| i.consume_shortcut(shortcut))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
+
+/// The first key pressed this frame with its modifiers, for rebinding.
+pub fn captured_shortcut(ctx: &egui::Context) -> Option<KeyboardShortcut> {
+    ctx.input(|i| {
+        i.events.iter().find_map(|event| match event {
+            egui::Event::Key { key, pressed: true, modifiers, .. } => Some(KeyboardShortcut::new(*modifiers, *key)),
+            _ => None,
+        })
+    })
+}
+
+fn modifier_count(modifiers: Modifiers) -> usize {
+    [modifiers.alt, modifiers.ctrl || modifiers.command || modifiers.mac_cmd, modifiers.shift]
+        .into_iter()
+        .filter(|&held| held)
+        .count()
+}
+
+/// Whether pressing it in a text field would type something.
+fn types_text(shortcut: &KeyboardShortcut) -> bool {
+    let modifiers = shortcut.modifiers;
+    let is_function_key = shortcut.logical_key.name().strip_prefix('F').is_some_and(|n| n.parse::<u8>().is_ok());
+    !(modifiers.alt || modifiers.ctrl || modifiers.command || modifiers.mac_cmd || is_function_key)
+}
@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+
+use chrono::NaiveDate;
+
+use crate::series::Candle;
+
+/// A technical indicator and its parameters.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Indicator {
+    Sma { period: usize },
+    Ema { period: usize },
+    /// Volume-weighted average price, restarting every UTC day like the sessions.
+    Vwap,
+    Bollinger { period: usize, width: f64 },
+    Rsi { period: usize },
+    Macd { fast: usize, slow: usize, signal: usize },
+    Atr { period: usize },
+}
+
+impl Indicator {
+    /// Every kind with its usual parameters.
+    pub const ALL: [Indicator; 7] = [
+        Indicator::Sma { period: 20 },
+        Indicator::Ema { period: 20 },
+        Indicator::Vwap,
+        Indicator::Bollinger { period: 20, width: 2.0 },
+        Indicator::Rsi { period: 14 },
+        Indicator::Macd { fast: 12, slow: 26, signal: 9 },
+        Indicator::Atr { period: 14 },
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Indicator::Sma { .. } => "SMA",
+            Indicator::Ema { .. } => "EMA",
+            Indicator::Vwap => "VWAP",
+            Indicator::Bollinger { .. } => "BB",
+            Indicator::Rsi { .. } => "RSI",
+            Indicator::Macd { .. } => "MACD",
+            Indicator::Atr { .. } => "ATR",
+        }
+    }
+
+    /// Name and parameters, e.g. "BB(20, 2)".
+    pub fn label(&self) -> String {
+        match self {
+            Indicator::Sma { period } | Indicator::Ema { period } | Indicator::Rsi { period } | Indicator::Atr { period } => {
+                format!("{}({period})", self.name())
+            }
+            Indicator::Vwap => self.name().to_owned(),
+            Indicator::Bollinger { period, width } => format!("{}({period}, {width})", self.name()),
+            Indicator::Macd { fast, slow, signal } => format!("{}({fast}, {slow}, {signal})", self.name()),
+        }
+    }
+
+    /// Drawn over the prices, as opposed to in a pane of its own below them.
+    pub fn is_overlay(&self) -> bool {
+        matches!(self, Indicator::Sma { .. } | Indicator::Ema { .. } | Indicator::Vwap | Indicator::Bollinger { .. })
+    }
+
+    /// What each of the values per bar is.
+    pub fn outputs(&self) -> &'static [&'static str] {
+        match self {
+            Indicator::Bollinger { .. } => &["Middle", "Upper", "Lower"],
+            Indicator::Macd { .. } => &["MACD", "Signal", "Histogram"],
+            _ => &["Value"],
+        }
+    }
+
+    fn state(&self) -> State {
+        match *self {
+            Indicator::Sma { period } => State::Sma(Window::new(period)),
+            Indicator::Ema { period } => State::Ema(Smoother::ema(period)),
+            Indicator::Vwap => State::Vwap { session: None, price_volume: 0.0, volume: 0.0 },
+            Indicator::Bollinger { period, width } => State::Bollinger { window: Window::new(period), width },
+            Indicator::Rsi { period } => State::Rsi {
+                previous_close: None,
+                gain: Smoother::wilder(period),
+                loss: Smoother::wilder(period),
+            },
+            Indicator::Macd { fast, slow, signal } => State::Macd {
+                fast: Smoother::ema(fast),
+                slow: Smoother::ema(slow),
+                signal: Smoother::ema(signal),
+            },
+            Indicator::Atr { period } => State::Atr { previous_close: None, average: Smoother::wilder(period) },
+        }
+    }
+}
+
+/// Up to three values per bar, see [`Indicator::outputs`]. `None` while warming up.
+pub type Values = [Option<f64>; 3];
+
+/// An indicator's values over a run of candles. Bars get added and the last one keeps
+/// changing while it forms, so the state before the last bar is kept and only what's new is
+/// computed on each update.
+#[derive(Clone)]
+pub struct IndicatorSeries {
+    indicator: Indicator,
+    // after every candle but the last
+    state: State,
+    // the candle `state` ends with, to notice when history was rewritten under us
+    settled: Option<Candle>,
+    values: Vec<Values>,
+}
+
+impl IndicatorSeries {
+    pub fn new(indicator: Indicator) -> Self {
+        Self { indicator, state: indicator.state(), settled: None, values: Vec::new() }
+    }
+
+    pub fn indicator(&self) -> Indicator {
+        self.indicator
+    }
+
+    /// Brings the values up to date with `candles`, one per candle.
+    pub fn update(&mut self, candles: &[Candle]) -> &[Values] {
+        let mut settled = self.values.len().saturating_sub(1);
+        let intact = candles.len() >= self.values.len() && (settled == 0 || self.settled == Some(candles[settled - 1]));
+        if !intact {
+            *self = Self::new(self.indicator);
+            settled = 0;
+        }
+
+        self.values.truncate(settled);
+        let mut state = self.state.clone();
+        for (index, candle) in candles.iter().enumerate().skip(settled) {
+            if index + 1 == candles.len() {
+                self.state = state.clone();
+                self.settled = index.checked_sub(1).map(|previous| candles[previous]);
+            }
+            self.values.push(state.push(candle));
+        }
+        &self.values
+    }
+
+    pub fn values(&self) -> &[Values] {
+        &self.values
+    }
+}
+
+#[derive(Clone)]
+enum State {
+    Sma(Window),
+    Ema(Smoother),
+    Vwap { session: Option<NaiveDate>, price_volume: f64, volume: f64 },
+    Bollinger { window: Window, width: f64 },
+    Rsi { previous_close: Option<f64>, gain: Smoother, loss: Smoother },
+    Macd { fast: Smoother, slow: Smoother, signal: Smoother },
+    Atr { previous_close: Option<f64>, average: Smoother },
+}
+
+impl State {
+    fn push(&mut self, candle: &Candle) -> Values {
+        match self {
+            State::Sma(window) => [window.push(candle.close), None, None],
+            State::Ema(ema) => [ema.push(candle.close), None, None],
+            State::Vwap { session, price_volume, volume } => {
+                let day = candle.time.date_naive();
+                if *session != Some(day) {
+                    *session = Some(day);
+                    *price_volume = 0.0;
+                    *volume = 0.0;
+                }
+                let typical = (candle.high + candle.low + candle.close) / 3.0;
+                *price_volume += typical * candle.volume;
+                *volume += candle.volume;
+                [(*volume > 0.0).then(|| *price_volume / *volume), None, None]
+            }
+            State::Bollinger { window, width } => {
+                let Some(middle) = window.push(candle.close) else {
+                    return [None, None, None];
+                };
+                let deviation = window.std_dev(middle) * *width;
+                [Some(middle), Some(middle + deviation), Some(middle - deviation)]
+            }
+            State::Rsi { previous_close, gain, loss } => {
+                let Some(previous) = previous_close.replace(candle.close) else {
+                    return [None, None, None];
+                };
+                let change = candle.close - previous;
+                let (Some(gain), Some(loss)) = (gain.push(change.max(0.0)), loss.push((-change).max(0.0))) else {
+                    return [None, None, None];
+                };
+                let rsi = if loss == 0.0 { 100.0 } else { 100.0 - 100.0 / (1.0 + gain / loss) };
+                [Some(rsi), None, None]
+            }
+            State::Macd { fast, slow, signal } => {
+                let (Some(fast), Some(slow)) = (fast.push(candle.close), slow.push(candle.close)) else {
+                    return [None, None, None];
+                };
+                let macd = fast - slow;
+                let signal = signal.push(macd);
+                [Some(macd), signal, signal.map(|signal| macd - signal)]
+            }
+            State::Atr { previous_close, average } => {
+                let range = match previous_close.replace(candle.close) {
+                    Some(previous) => {
+                        (candle.high - candle.low).max((candle.high - previous).abs()).max((candle.low - previous).abs())
+                    }
+                    None => candle.high - candle.low,
+                };
+                [average.push(range), None, None]
+            }
+        }
+    }
+}
+
+/// The last `period` values, for simple averages.
+#[derive(Clone)]
+struct Window {
+    period: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Window {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self { period, values: VecDeque::with_capacity(period + 1), sum: 0.0 }
+    }
+
+    /// The average once there are `period` values.
+    fn push(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        self.sum += value;
+        if self.values.len() > self.period {
+            self.sum -= self.values.pop_front().unwrap_or_default();
+        }
+        (self.values.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+
+    fn std_dev(&self, mean: f64) -> f64 {
+        let variance = self.values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / self.values.len() as f64;
+        variance.sqrt()
+    }
+}
+
+/// Exponential smoothing, seeded with the simple average of the first `period` values.
+#[derive(Clone)]
+struct Smoother {
+    period: usize,
+    alpha: f64,
+    value: Option<f64>,
+    seed: f64,
+    seen: usize,
+}
+
+impl Smoother {
+    fn ema(period: usize) -> Self {
+        Self::with_alpha(period, 2.0 / (period.max(1) as f64 + 1.0))
+    }
+
+    /// Wilder's smoothing, as RSI and ATR use it.
+    fn wilder(period: usize) -> Self {
+        Self::with_alpha(period, 1.0 / period.max(1) as f64)
+    }
+
+    fn with_alpha(period: usize, alpha: f64) -> Self {
+        Self { period: period.max(1), alpha, value: None, seed: 0.0, seen: 0 }
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(current) => Some(current + self.alpha * (value - current)),
+            None => {
+                self.seed += value;
+                self.seen += 1;
+                (self.seen == self.period).then(|| self.seed / self.period as f64)
+            }
+        };
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::*;
+
+    fn candles(count: usize) -> Vec<Candle> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 20, 0, 0).unwrap();
+        (0..count)
+            .map(|index| {
+                let close = 100.0 + (index as f64 * 0.7).sin() * 5.0 + index as f64 * 0.1;
+                // hourly, so VWAP crosses a session boundary
+                let time = start + Duration::hours(index as i64);
+                Candle {
+                    x: time.timestamp_millis() as f64,
+                    time,
+                    open: close - 0.5,
+                    high: close + 1.0,
+                    low: close - 1.5,
+                    close,
+                    volume: 100.0 + index as f64,
+                }
+            })
+            .collect()
+    }
+
+    fn close_enough(a: &[Values], b: &[Values]) -> bool {
+        a.len() == b.len()
+            && a.iter().flatten().zip(b.iter().flatten()).all(|(a, b)| match (a, b) {
+                (Some(a), Some(b)) => (a - b).abs() < 1e-9,
+                (a, b) => a.is_none() && b.is_none(),
+            })
+    }
+
+    #[test]
+    fn incremental_matches_a_full_recompute() {
+        let candles = candles(60);
+        for indicator in Indicator::ALL {
+            let mut series = IndicatorSeries::new(indicator);
+            for end in 1..=candles.len() {
+                // the last bar forms over a few updates before the next one opens
+                let mut forming = candles[end - 1];
+                for close in [forming.close - 1.0, forming.close + 0.5, candles[end - 1].close] {
+                    forming.close = close;
+                    let mut partial = candles[..end].to_vec();
+                    partial[end - 1] = forming;
+                    series.update(&partial);
+                }
+            }
+            let full = IndicatorSeries::new(indicator).update(&candles).to_vec();
+            assert!(close_enough(series.values(), &full), "{}", indicator.label());
+        }
+    }
+
+    #[test]
+    fn starts_over_when_history_changes() {
+        let mut candles = candles(30);
+        let mut series = IndicatorSeries::new(Indicator::Ema { period: 5 });
+        series.update(&candles);
+        // a late point lands in the middle, moving everything after it along
+        let mut late = candles[3];
+        late.close += 10.0;
+        candles.insert(3, late);
+        series.update(&candles);
+        let full = IndicatorSeries::new(Indicator::Ema { period: 5 }).update(&candles).to_vec();
+        assert!(close_enough(series.values(), &full));
+
+        series.update(&candles[..10]);
+        assert_eq!(series.values().len(), 10);
+    }
+
+    #[test]
+    fn warms_up_before_giving_values() {
+        let candles = candles(30);
+        let values = IndicatorSeries::new(Indicator::Sma { period: 3 }).update(&candles).to_vec();
+        assert_eq!(values[1][0], None);
+        let expected = (candles[0].close + candles[1].close + candles[2].close) / 3.0;
+        assert!((values[2][0].unwrap() - expected).abs() < 1e-9);
+
+        let values = IndicatorSeries::new(Indicator::Rsi { period: 14 }).update(&candles).to_vec();
+        assert_eq!(values[13][0], None);
+        assert!(values[14][0].is_some_and(|rsi| (0.0..=100.0).contains(&rsi)));
+    }
+
+    #[test]
+    fn vwap_restarts_every_day() {
+        let candles = candles(10);
+        let values = IndicatorSeries::new(Indicator::Vwap).update(&candles).to_vec();
+        // 20:00 to 23:00 on the 2nd, then midnight starts the 3rd
+        let first = &candles[4];
+        let typical = (first.high + first.low + first.close) / 3.0;
+        assert!((values[4][0].unwrap() - typical).abs() < 1e-9);
+    }
+}
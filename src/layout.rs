@@ -0,0 +1,49 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{charts::ChartType, drawings::Drawings, indicators::Indicator, scale::ScaleMode, series::Timeframe, stock::CandleStyle, Stock};
+
+/// Where the File menu saves and loads the layout, next to where the app was started.
+pub const LAYOUT_FILE: &str = "layout.json";
+
+/// A stock window as the layout file keeps it: how the chart looks and what's drawn on it.
+/// Prices, quotes and the order ticket aren't part of a layout, they'd be stale by the time it's
+/// loaded.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ChartLayout {
+    pub open: bool,
+    pub chart_type: ChartType,
+    pub box_size: f64,
+    pub scale_mode: ScaleMode,
+    pub comparisons: Vec<String>,
+    pub volume_toggle: bool,
+    pub candle_style: CandleStyle,
+    pub pane_ratio: f32,
+    pub timeframe: Timeframe,
+    pub indicators: Vec<Indicator>,
+    pub drawings: Drawings,
+}
+
+impl Default for ChartLayout {
+    fn default() -> Self {
+        Stock::default("").chart_layout()
+    }
+}
+
+/// Writes every stock window's chart settings, indicators and drawings as JSON.
+pub fn save(stocks: &HashMap<String, Arc<Mutex<Stock>>>, path: &Path) -> Result<(), String> {
+    let layout: BTreeMap<&String, ChartLayout> =
+        stocks.iter().map(|(symbol, stock)| (symbol, stock.lock().unwrap().chart_layout())).collect();
+    let json = serde_json::to_string_pretty(&layout).map_err(|error| error.to_string())?;
+    std::fs::write(path, json).map_err(|error| format!("Couldn't write {}: {error}", path.display()))
+}
+
+/// The stock windows saved by [`save`], by symbol.
+pub fn load(path: &Path) -> Result<BTreeMap<String, ChartLayout>, String> {
+    let json = std::fs::read_to_string(path).map_err(|error| format!("Couldn't read {}: {error}", path.display()))?;
+    serde_json::from_str(&json).map_err(|error| format!("{} isn't a layout: {error}", path.display()))
+}
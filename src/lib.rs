@@ -1,7 +1,43 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod backend;
+mod charts;
+mod client;
+mod command;
+mod connection;
+mod drawings;
+mod hotkeys;
+mod indicators;
+mod layout;
+mod orders;
+mod portfolio;
+mod price;
+mod risk;
+mod scale;
+mod series;
+mod simulation;
 mod stock;
+mod stream;
+mod ticket;
 pub use app::TemplateApp;
+pub use backend::BackendProfile;
+pub use charts::ChartType;
+pub use client::{ClientError, HttpBackend, MockBackend, OrderAck, OrderRequest, TradingBackend};
+pub use command::OrderCommand;
+pub use connection::{ConnectionMonitor, ConnectionState};
+pub use drawings::{Drawing, Drawings, Shape, Tool};
+pub use hotkeys::{Action, Hotkeys, Quote};
+pub use indicators::{Indicator, IndicatorSeries, Values};
+pub use orders::{Bracket, BracketLeg, BracketSpec, BracketState, Fill, Order, OrderBook, OrderSpec, OrderStatus, OrderType, Side, TimeInForce};
+pub use portfolio::{Mark, Portfolio, Position};
+pub use price::{InstrumentSpec, Instruments, Price};
+pub use risk::{Exposure, RiskLimits};
+pub use scale::{ScaleMode, YScale};
+pub use series::{Candle, Resampled, SeriesBuffer, Timeframe};
+pub use simulation::SimulationState;
+pub use stock::Crosshair;
 pub use stock::Stock;
 pub use stock::create_new_stock_window;
+pub use stream::{FeedMessage, FeedState, MarketFeed};
+pub use ticket::{OrderTicket, TradingContext};
@@ -0,0 +1,1238 @@
+use std::{
+    fmt,
+    hash::{BuildHasher, Hasher, RandomState},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use egui::Color32;
+
+use crate::{
+    client::{ClientError, OrderAck, OrderRequest, TradingBackend},
+    price::Price,
+};
+
+/// How often open orders are polled for status changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// A submit can't take longer than the longest timeout the settings allow, an order still
+/// pending after this lost its answer and gets looked up.
+const ACK_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Side {
+    #[default]
+    Buy,
+    Sell,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Buy => write!(f, "BUY"),
+            Side::Sell => write!(f, "SELL"),
+        }
+    }
+}
+
+impl Side {
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            Side::Buy => Color32::from_rgb(0, 200, 0),
+            Side::Sell => Color32::from_rgb(220, 0, 0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    Market,
+    #[default]
+    Limit,
+    Stop,
+    StopLimit,
+}
+
+impl OrderType {
+    pub const ALL: [OrderType; 4] = [OrderType::Market, OrderType::Limit, OrderType::Stop, OrderType::StopLimit];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrderType::Market => "Market",
+            OrderType::Limit => "Limit",
+            OrderType::Stop => "Stop",
+            OrderType::StopLimit => "Stop-Limit",
+        }
+    }
+
+    pub fn has_limit(&self) -> bool {
+        matches!(self, OrderType::Limit | OrderType::StopLimit)
+    }
+
+    pub fn has_stop(&self) -> bool {
+        matches!(self, OrderType::Stop | OrderType::StopLimit)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    #[default]
+    Day,
+    Gtc,
+    Ioc,
+    Fok,
+}
+
+impl TimeInForce {
+    pub const ALL: [TimeInForce; 4] = [TimeInForce::Day, TimeInForce::Gtc, TimeInForce::Ioc, TimeInForce::Fok];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimeInForce::Day => "DAY",
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Ioc => "IOC",
+            TimeInForce::Fok => "FOK",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TimeInForce::Day => "Day: expires at the end of the session",
+            TimeInForce::Gtc => "Good 'til cancelled",
+            TimeInForce::Ioc => "Immediate or cancel: fill what you can now, cancel the rest",
+            TimeInForce::Fok => "Fill or kill: fill all of it now or none of it",
+        }
+    }
+}
+
+/// The part an order plays in a bracket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BracketLeg {
+    Entry,
+    TakeProfit,
+    StopLoss,
+}
+
+impl BracketLeg {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BracketLeg::Entry => "Entry",
+            BracketLeg::TakeProfit => "TP",
+            BracketLeg::StopLoss => "SL",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            BracketLeg::Entry => Color32::from_rgb(100, 150, 255),
+            BracketLeg::TakeProfit => Color32::from_rgb(0, 200, 0),
+            BracketLeg::StopLoss => Color32::from_rgb(220, 0, 0),
+        }
+    }
+}
+
+/// Exit levels for a bracket: once the entry starts filling, a limit at `take_profit` and a
+/// stop at `stop_loss` go out for the filled quantity, and fills on either shrink the other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BracketSpec {
+    pub take_profit: Price,
+    pub stop_loss: Price,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BracketState {
+    /// Waiting for the entry to fill.
+    Entry,
+    /// Exit legs are out, the entry may still be filling.
+    Exits,
+    Closed,
+}
+
+/// An entry and its take-profit/stop-loss legs, linked by client id. The server knows
+/// nothing about the link, keeping the legs in step happens in [`manage_brackets`].
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Bracket {
+    pub symbol: String,
+    pub entry: String,
+    pub entry_price: Price,
+    pub take_profit: Price,
+    pub stop_loss: Price,
+    pub take_profit_order: Option<String>,
+    pub stop_loss_order: Option<String>,
+    pub state: BracketState,
+    // what the entry had filled when last seen, for going on without it
+    #[serde(default)]
+    pub entry_filled: u32,
+}
+
+impl Bracket {
+    /// Exit prices worth drawing on the chart before the exit orders are out; after that the
+    /// orders are drawn themselves.
+    pub fn levels(&self) -> Vec<(BracketLeg, Price)> {
+        match self.state {
+            BracketState::Entry => vec![(BracketLeg::TakeProfit, self.take_profit), (BracketLeg::StopLoss, self.stop_loss)],
+            BracketState::Exits | BracketState::Closed => Vec::new(),
+        }
+    }
+}
+
+/// What the user asked for, before it's been sent anywhere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderSpec {
+    pub symbol: String,
+    pub side: Side,
+    pub qty: u32,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub limit_price: Option<Price>,
+    pub stop_price: Option<Price>,
+    /// Expected fill price: the limit if there is one, otherwise the stop or the last price.
+    pub price: Price,
+    pub leg: Option<BracketLeg>,
+}
+
+impl OrderSpec {
+    /// e.g. "Stop-Limit GTC, stop $101.00, limit $100.50".
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} {}", self.order_type.label(), self.time_in_force.label());
+        if let Some(stop) = self.stop_price {
+            text += &format!(", stop ${stop}");
+        }
+        if let Some(limit) = self.limit_price {
+            text += &format!(", limit ${limit}");
+        }
+        text
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum OrderStatus {
+    /// Sent, no answer yet.
+    Pending,
+    /// Sent, but the answer got lost or came too late, so the server may well have it. Looked
+    /// up by client id until we know.
+    Unconfirmed(String),
+    Accepted,
+    Rejected(String),
+    PartiallyFilled,
+    Filled,
+    /// Cancel sent, no answer yet.
+    CancelPending,
+    Cancelled,
+    /// New price sent, no answer yet.
+    ReplacePending,
+}
+
+impl OrderStatus {
+    pub fn label(&self) -> &str {
+        match self {
+            OrderStatus::Pending => "Pending",
+            OrderStatus::Unconfirmed(_) => "Unconfirmed",
+            OrderStatus::Accepted => "Accepted",
+            OrderStatus::Rejected(_) => "Rejected",
+            OrderStatus::PartiallyFilled => "Partially filled",
+            OrderStatus::Filled => "Filled",
+            OrderStatus::CancelPending => "Cancelling",
+            OrderStatus::Cancelled => "Cancelled",
+            OrderStatus::ReplacePending => "Modifying",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            OrderStatus::Rejected(_) => Color32::from_rgb(255, 0, 0),
+            OrderStatus::Filled => Color32::from_rgb(0, 255, 0),
+            OrderStatus::PartiallyFilled => Color32::from_rgb(150, 255, 150),
+            OrderStatus::Cancelled => Color32::GRAY,
+            _ => Color32::from_rgb(255, 165, 0),
+        }
+    }
+
+    /// Still working on the exchange, as opposed to done one way or another.
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Pending
+                | OrderStatus::Unconfirmed(_)
+                | OrderStatus::Accepted
+                | OrderStatus::PartiallyFilled
+                | OrderStatus::CancelPending
+                | OrderStatus::ReplacePending
+        )
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Order {
+    pub client_id: String,
+    // assigned by the server once it accepts the order; older servers don't send one
+    pub server_id: Option<String>,
+    pub symbol: String,
+    pub side: Side,
+    pub qty: u32,
+    // limit price, or what we expected to pay for market and stop orders
+    pub price: Price,
+    #[serde(default)]
+    pub order_type: OrderType,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub stop_price: Option<Price>,
+    #[serde(default)]
+    pub leg: Option<BracketLeg>,
+    pub filled_qty: u32,
+    pub avg_fill_price: Price,
+    pub status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Order {
+    /// The order as it would be sent now, for replacing it.
+    pub fn spec(&self) -> OrderSpec {
+        OrderSpec {
+            symbol: self.symbol.clone(),
+            side: self.side,
+            qty: self.qty,
+            order_type: self.order_type,
+            time_in_force: self.time_in_force,
+            limit_price: self.order_type.has_limit().then_some(self.price),
+            stop_price: self.stop_price,
+            price: self.price,
+            leg: self.leg,
+        }
+    }
+
+    /// Whether the server knows it by id and it's not busy with a cancel or another move.
+    pub fn is_movable(&self) -> bool {
+        self.server_id.is_some() && matches!(self.status, OrderStatus::Accepted | OrderStatus::PartiallyFilled)
+    }
+
+    /// Limit and stop lines for the chart, whichever the type has.
+    fn levels(&self) -> Vec<OrderLevel> {
+        let level = |kind, price| OrderLevel {
+            client_id: self.client_id.clone(),
+            side: self.side,
+            qty: self.qty - self.filled_qty.min(self.qty),
+            kind,
+            price,
+            leg: self.leg,
+            movable: self.is_movable(),
+        };
+        let mut levels = Vec::new();
+        if self.order_type.has_limit() {
+            levels.push(level(LevelKind::Limit, self.price));
+        }
+        if let Some(stop) = self.stop_price.filter(|_| self.order_type.has_stop()) {
+            levels.push(level(LevelKind::Stop, stop));
+        }
+        levels
+    }
+}
+
+/// Which of an order's prices a chart line stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelKind {
+    Limit,
+    Stop,
+}
+
+impl LevelKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LevelKind::Limit => "LMT",
+            LevelKind::Stop => "STP",
+        }
+    }
+}
+
+/// A working order's limit or stop price, as drawn on the chart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderLevel {
+    pub client_id: String,
+    pub side: Side,
+    /// What's left to fill.
+    pub qty: u32,
+    pub kind: LevelKind,
+    pub price: Price,
+    pub leg: Option<BracketLeg>,
+    /// Whether dragging the line can move the order, see [`Order::is_movable`].
+    pub movable: bool,
+}
+
+impl OrderLevel {
+    /// e.g. "BUY 100 LMT 174.20", with the bracket leg in front for exits.
+    pub fn label(&self) -> String {
+        let text = format!("{} {} {} {}", self.side, self.qty, self.kind.label(), self.price);
+        match self.leg {
+            Some(leg) if leg != BracketLeg::Entry => format!("{} {text}", leg.label()),
+            _ => text,
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self.leg {
+            Some(leg @ (BracketLeg::TakeProfit | BracketLeg::StopLoss)) => leg.color(),
+            _ => self.side.color(),
+        }
+    }
+}
+
+/// One execution, as derived from the change in an order's filled quantity.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Fill {
+    pub client_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub qty: u32,
+    pub price: Price,
+    pub at: DateTime<Utc>,
+}
+
+/// Every order submitted from this app, open and historical. Request callbacks update it, so
+/// it's shared behind an `Arc<Mutex<_>>`.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct OrderBook {
+    // oldest first
+    orders: Vec<Order>,
+    fills: Vec<Fill>,
+    brackets: Vec<Bracket>,
+    next_id: u64,
+    // goes in front of the counter so client ids don't repeat across runs, installs and resets
+    #[serde(skip)]
+    session: String,
+    #[serde(skip)]
+    last_poll: Option<DateTime<Utc>>,
+}
+
+impl OrderBook {
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    pub fn get(&self, client_id: &str) -> Option<&Order> {
+        self.orders.iter().find(|order| order.client_id == client_id)
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.iter().filter(|order| order.status.is_open())
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Limit and stop lines of the open orders on `symbol`.
+    pub fn levels(&self, symbol: &str) -> Vec<OrderLevel> {
+        self.open_orders().filter(|order| order.symbol == symbol).flat_map(Order::levels).collect()
+    }
+
+    /// Brackets on `symbol` that still have something working.
+    pub fn active_brackets<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a Bracket> {
+        self.brackets
+            .iter()
+            .filter(move |bracket| bracket.symbol == symbol && bracket.state != BracketState::Closed)
+    }
+
+    /// Forgets finished orders and brackets, open ones stay. So do the orders of a bracket that
+    /// isn't closed, a filled entry is what its exits are sized from.
+    pub fn clear_history(&mut self) {
+        self.brackets.retain(|bracket| bracket.state != BracketState::Closed);
+        let brackets = &self.brackets;
+        self.orders.retain(|order| {
+            order.status.is_open()
+                || brackets.iter().any(|bracket| {
+                    bracket.entry == order.client_id
+                        || bracket.take_profit_order.as_ref() == Some(&order.client_id)
+                        || bracket.stop_loss_order.as_ref() == Some(&order.client_id)
+                })
+        });
+    }
+
+    /// Whether the leg is still working and not already being cancelled.
+    fn is_cancellable(&self, client_id: Option<&String>) -> bool {
+        client_id
+            .and_then(|id| self.get(id))
+            .is_some_and(|order| order.status.is_open() && order.status != OrderStatus::CancelPending)
+    }
+
+    fn get_mut(&mut self, client_id: &str) -> Option<&mut Order> {
+        self.orders.iter_mut().find(|order| order.client_id == client_id)
+    }
+
+    fn new_order(&mut self, spec: &OrderSpec) -> Order {
+        if self.session.is_empty() {
+            self.session = session_id();
+        }
+        self.next_id += 1;
+        let now = Utc::now();
+        let order = Order {
+            client_id: format!("C{}-{:06}", self.session, self.next_id),
+            server_id: None,
+            symbol: spec.symbol.clone(),
+            side: spec.side,
+            qty: spec.qty,
+            price: spec.price,
+            order_type: spec.order_type,
+            time_in_force: spec.time_in_force,
+            stop_price: spec.stop_price,
+            leg: spec.leg,
+            filled_qty: 0,
+            avg_fill_price: Price::ZERO,
+            status: OrderStatus::Pending,
+            created_at: now,
+            updated_at: now,
+        };
+        self.orders.push(order.clone());
+        order
+    }
+
+    /// Applies a server acknowledgement, recording a fill if the filled quantity went up.
+    fn apply_ack(&mut self, client_id: &str, ack: OrderAck) {
+        let Some(order) = self.get_mut(client_id) else {
+            return;
+        };
+        if ack.id.is_some() {
+            order.server_id = ack.id;
+        }
+
+        let previous_qty = order.filled_qty;
+        let previous_cost = order.avg_fill_price.times(previous_qty as i64);
+        let filled_qty = ack.filled_qty.unwrap_or(match ack.status.as_deref() {
+            Some("filled") => order.qty,
+            _ => previous_qty,
+        });
+        let fill = if filled_qty > previous_qty {
+            let qty = filled_qty - previous_qty;
+            // without an average we assume the order filled at its limit
+            let avg_price = ack.avg_price.map_or(order.price, Price::from_f64);
+            let price = (avg_price.times(filled_qty as i64) - previous_cost).per(qty as i64);
+            order.filled_qty = filled_qty;
+            order.avg_fill_price = avg_price;
+            Some(Fill {
+                client_id: order.client_id.clone(),
+                symbol: order.symbol.clone(),
+                side: order.side,
+                qty,
+                price,
+                at: Utc::now(),
+            })
+        } else {
+            None
+        };
+
+        order.status = match ack.status.as_deref() {
+            Some("rejected") => OrderStatus::Rejected(ack.reason.unwrap_or_else(|| "rejected by server".to_owned())),
+            Some("cancelled" | "canceled") => OrderStatus::Cancelled,
+            Some("filled") => OrderStatus::Filled,
+            _ if order.filled_qty >= order.qty => OrderStatus::Filled,
+            Some("partially_filled") => OrderStatus::PartiallyFilled,
+            _ if order.filled_qty > 0 => OrderStatus::PartiallyFilled,
+            // anything else (including a plain-text answer) means the server took it
+            _ if order.status == OrderStatus::CancelPending => OrderStatus::CancelPending,
+            _ => OrderStatus::Accepted,
+        };
+        order.updated_at = Utc::now();
+
+        if let Some(fill) = fill {
+            self.fills.push(fill);
+        }
+    }
+
+    /// Sets the limit or stop of an order, keeping its bracket's exit levels in step.
+    fn set_level(&mut self, client_id: &str, kind: LevelKind, price: Price) {
+        let Some(order) = self.get_mut(client_id) else {
+            return;
+        };
+        match kind {
+            LevelKind::Limit => order.price = price,
+            LevelKind::Stop => {
+                order.stop_price = Some(price);
+                if !order.order_type.has_limit() {
+                    order.price = price;
+                }
+            }
+        }
+        for bracket in &mut self.brackets {
+            if bracket.take_profit_order.as_deref() == Some(client_id) {
+                bracket.take_profit = price;
+            }
+            if bracket.stop_loss_order.as_deref() == Some(client_id) {
+                bracket.stop_loss = price;
+            }
+        }
+    }
+
+    fn set_status(&mut self, client_id: &str, status: OrderStatus) {
+        if let Some(order) = self.get_mut(client_id) {
+            order.status = status;
+            order.updated_at = Utc::now();
+        }
+    }
+}
+
+/// Random per run where the platform has randomness, and the time for where it doesn't.
+fn session_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_i64(Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    format!("{:08X}", hasher.finish() as u32)
+}
+
+/// Records a new order and sends it. Returns the client id to follow it up with.
+pub fn submit(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context, spec: OrderSpec) -> String {
+    let order = book.lock().unwrap().new_order(&spec);
+    log::info!("Submitting order {}: {} {} {} ({})", order.client_id, spec.side, spec.qty, spec.symbol, spec.describe());
+    let request = OrderRequest {
+        client_order_id: order.client_id.clone(),
+        spec,
+    };
+
+    let book_clone = Arc::clone(book);
+    let client_id = order.client_id.clone();
+    let ctx = ctx.clone();
+    backend.submit_order(&request, Box::new(move |result| {
+        let mut book = book_clone.lock().unwrap();
+        match result {
+            Ok(ack) => book.apply_ack(&client_id, ack),
+            // it may have got there anyway, the poll finds out
+            Err(e @ (ClientError::Timeout { .. } | ClientError::Network(_))) => {
+                log::warn!("Order {client_id} unconfirmed: {e}");
+                book.set_status(&client_id, OrderStatus::Unconfirmed(e.to_string()));
+            }
+            Err(e) => {
+                log::error!("Order {client_id} failed: {e}");
+                book.set_status(&client_id, OrderStatus::Rejected(e.to_string()));
+            }
+        }
+        ctx.request_repaint();
+    }));
+
+    order.client_id
+}
+
+/// Sends the entry of a bracket; the exits follow from [`manage_brackets`] as it fills.
+pub fn submit_bracket(
+    book: &Arc<Mutex<OrderBook>>,
+    backend: &dyn TradingBackend,
+    ctx: &egui::Context,
+    mut spec: OrderSpec,
+    bracket: BracketSpec,
+) -> String {
+    spec.leg = Some(BracketLeg::Entry);
+    let symbol = spec.symbol.clone();
+    let entry_price = spec.price;
+    let entry = submit(book, backend, ctx, spec);
+    book.lock().unwrap().brackets.push(Bracket {
+        symbol,
+        entry: entry.clone(),
+        entry_price,
+        take_profit: bracket.take_profit,
+        stop_loss: bracket.stop_loss,
+        take_profit_order: None,
+        stop_loss_order: None,
+        state: BracketState::Entry,
+        entry_filled: 0,
+    });
+    entry
+}
+
+/// Moves brackets along: sends the exit legs as soon as the entry starts filling and keeps
+/// both sized to what's still held, growing them as the entry fills and shrinking each as the
+/// other fills. Once the position is closed whatever is left working gets cancelled. A leg that
+/// fails takes nothing down with it, a stop stays a stop whatever happened to the take-profit.
+pub fn manage_brackets(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    let mut exits = Vec::new();
+    let mut resizes = Vec::new();
+    let mut cancels = Vec::new();
+    {
+        let mut guard = book.lock().unwrap();
+        let mut changes = Vec::new();
+        let mut entry_fills = Vec::new();
+        for (index, bracket) in guard.brackets.iter().enumerate() {
+            let entry = guard.get(&bracket.entry);
+            // an entry gone from the book has filled all it's going to
+            let entry_filled = entry.map_or(bracket.entry_filled, |entry| entry.filled_qty);
+            let entry_open = entry.is_some_and(|entry| entry.status.is_open());
+            if entry_filled != bracket.entry_filled {
+                entry_fills.push((index, entry_filled));
+            }
+            match bracket.state {
+                BracketState::Entry => {
+                    let Some(entry) = entry else {
+                        changes.push((index, BracketState::Closed));
+                        continue;
+                    };
+                    if entry.filled_qty > 0 {
+                        let exit = |leg, order_type, price| OrderSpec {
+                            symbol: bracket.symbol.clone(),
+                            side: entry.side.opposite(),
+                            qty: entry.filled_qty,
+                            order_type,
+                            time_in_force: TimeInForce::Gtc,
+                            limit_price: (order_type == OrderType::Limit).then_some(price),
+                            stop_price: (order_type == OrderType::Stop).then_some(price),
+                            price,
+                            leg: Some(leg),
+                        };
+                        exits.push((
+                            index,
+                            exit(BracketLeg::TakeProfit, OrderType::Limit, bracket.take_profit),
+                            exit(BracketLeg::StopLoss, OrderType::Stop, bracket.stop_loss),
+                        ));
+                        changes.push((index, BracketState::Exits));
+                    } else if !entry.status.is_open() {
+                        changes.push((index, BracketState::Closed));
+                    }
+                }
+                BracketState::Exits => {
+                    let legs: Vec<&Order> = [&bracket.take_profit_order, &bracket.stop_loss_order]
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|client_id| guard.get(client_id))
+                        .collect();
+                    let exited: u32 = legs.iter().map(|leg| leg.filled_qty).sum();
+                    let held = entry_filled.saturating_sub(exited);
+                    if held == 0 {
+                        // out of the position: the rest of the other leg would open a new one,
+                        // and so would the rest of the entry
+                        let working = legs.iter().map(|leg| &leg.client_id).chain(entry.map(|entry| &entry.client_id));
+                        cancels.extend(working.filter(|client_id| guard.is_cancellable(Some(client_id))).cloned());
+                    } else {
+                        // each leg covers what it has filled already plus what's still held
+                        for leg in legs.iter().filter(|leg| leg.is_movable()) {
+                            let qty = leg.filled_qty + held;
+                            if leg.qty != qty {
+                                resizes.push((leg.client_id.clone(), qty));
+                            }
+                        }
+                    }
+                    if !entry_open && !legs.iter().any(|leg| leg.status.is_open()) {
+                        if held > 0 {
+                            log::warn!("Bracket on {} has {held} shares left and no exits working", bracket.symbol);
+                        }
+                        changes.push((index, BracketState::Closed));
+                    }
+                }
+                BracketState::Closed => {}
+            }
+        }
+        for (index, state) in changes {
+            guard.brackets[index].state = state;
+        }
+        for (index, filled) in entry_fills {
+            guard.brackets[index].entry_filled = filled;
+        }
+    }
+
+    // the book has to be unlocked here, the mock backend answers synchronously
+    for (index, take_profit, stop_loss) in exits {
+        log::info!("Bracket on {} entered, sending exits for {}", take_profit.symbol, take_profit.qty);
+        let take_profit = submit(book, backend, ctx, take_profit);
+        let stop_loss = submit(book, backend, ctx, stop_loss);
+        let mut guard = book.lock().unwrap();
+        guard.brackets[index].take_profit_order = Some(take_profit);
+        guard.brackets[index].stop_loss_order = Some(stop_loss);
+    }
+    for (client_id, qty) in resizes {
+        resize(book, backend, ctx, &client_id, qty);
+    }
+    for client_id in cancels {
+        log::info!("Cancelling {client_id}, its bracket's position is closed");
+        cancel(book, backend, ctx, &client_id);
+    }
+}
+
+/// Asks the server to cancel an open order. Needs the server's id, so orders from servers
+/// that don't return one can't be cancelled from here.
+pub fn cancel(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context, client_id: &str) {
+    let (server_id, previous_status) = {
+        let mut guard = book.lock().unwrap();
+        let Some(order) = guard.get_mut(client_id) else {
+            return;
+        };
+        let Some(server_id) = order.server_id.clone().filter(|_| order.status.is_open()) else {
+            return;
+        };
+        let previous_status = std::mem::replace(&mut order.status, OrderStatus::CancelPending);
+        order.updated_at = Utc::now();
+        (server_id, previous_status)
+    };
+
+    let book = Arc::clone(book);
+    let client_id = client_id.to_owned();
+    let ctx = ctx.clone();
+    backend.cancel_order(&server_id, Box::new(move |result| {
+        let mut book = book.lock().unwrap();
+        match result {
+            Ok(ack) => book.apply_ack(&client_id, ack),
+            Err(e) => {
+                log::error!("Cancelling order {client_id} failed: {e}");
+                // we don't know better, it's presumably still working as it was
+                book.set_status(&client_id, previous_status);
+            }
+        }
+        ctx.request_repaint();
+    }));
+}
+
+/// Moves the limit or stop of a working order to `price` by replacing it on the server. The
+/// order shows the new price straight away and goes back to the old one if the replace fails.
+pub fn modify(
+    book: &Arc<Mutex<OrderBook>>,
+    backend: &dyn TradingBackend,
+    ctx: &egui::Context,
+    client_id: &str,
+    kind: LevelKind,
+    price: Price,
+) {
+    let (server_id, request, previous) = {
+        let mut guard = book.lock().unwrap();
+        let Some(order) = guard.get(client_id).filter(|order| order.is_movable()) else {
+            return;
+        };
+        let Some(server_id) = order.server_id.clone() else {
+            return;
+        };
+        let previous = match kind {
+            LevelKind::Limit => order.price,
+            LevelKind::Stop => order.stop_price.unwrap_or(order.price),
+        };
+        let status = order.status.clone();
+        guard.set_level(client_id, kind, price);
+        guard.set_status(client_id, OrderStatus::ReplacePending);
+        let Some(spec) = guard.get(client_id).map(Order::spec) else {
+            return;
+        };
+        log::info!("Moving order {client_id} {} from {previous} to {price}", kind.label());
+        (server_id, OrderRequest { client_order_id: client_id.to_owned(), spec }, (previous, status))
+    };
+
+    let (previous_price, previous_status) = previous;
+    replace(book, backend, ctx, &server_id, request, move |book, client_id| {
+        book.set_level(client_id, kind, previous_price);
+        book.set_status(client_id, previous_status);
+    });
+}
+
+/// Changes a working order's quantity by replacing it on the server, going back to the old one
+/// if the replace fails. For keeping bracket exits the size of the position.
+fn resize(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context, client_id: &str, qty: u32) {
+    let (server_id, request, previous) = {
+        let mut guard = book.lock().unwrap();
+        let Some(order) = guard.get_mut(client_id).filter(|order| order.is_movable()) else {
+            return;
+        };
+        let Some(server_id) = order.server_id.clone() else {
+            return;
+        };
+        let previous = (order.qty, order.status.clone());
+        log::info!("Resizing order {client_id} from {} to {qty}", order.qty);
+        order.qty = qty;
+        order.status = OrderStatus::ReplacePending;
+        order.updated_at = Utc::now();
+        (server_id, OrderRequest { client_order_id: client_id.to_owned(), spec: order.spec() }, previous)
+    };
+
+    let (previous_qty, previous_status) = previous;
+    replace(book, backend, ctx, &server_id, request, move |book, client_id| {
+        if let Some(order) = book.get_mut(client_id) {
+            order.qty = previous_qty;
+        }
+        book.set_status(client_id, previous_status);
+    });
+}
+
+/// Sends a replace the book already shows, calling `restore` to undo it there if it doesn't
+/// go through.
+fn replace(
+    book: &Arc<Mutex<OrderBook>>,
+    backend: &dyn TradingBackend,
+    ctx: &egui::Context,
+    server_id: &str,
+    request: OrderRequest,
+    restore: impl FnOnce(&mut OrderBook, &str) + Send + 'static,
+) {
+    let book = Arc::clone(book);
+    let client_id = request.client_order_id.clone();
+    let ctx = ctx.clone();
+    backend.replace_order(server_id, &request, Box::new(move |result| {
+        let mut book = book.lock().unwrap();
+        match result {
+            Ok(ack) if ack.status.as_deref() == Some("rejected") => {
+                // the original order is still there, as it was
+                log::error!("Replacing order {client_id} was rejected: {}", ack.reason.unwrap_or_default());
+                restore(&mut book, &client_id);
+            }
+            Ok(ack) => book.apply_ack(&client_id, ack),
+            Err(e) => {
+                log::error!("Replacing order {client_id} failed: {e}");
+                restore(&mut book, &client_id);
+            }
+        }
+        ctx.request_repaint();
+    }));
+}
+
+/// Cancels every open order we can.
+pub fn cancel_all(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    let open: Vec<String> = book.lock().unwrap().open_orders().map(|order| order.client_id.clone()).collect();
+    for client_id in open {
+        cancel(book, backend, ctx, &client_id);
+    }
+}
+
+/// Refreshes the status of open orders the server knows about, every `POLL_INTERVAL`, and
+/// looks up by client id unconfirmed ones and those pending for longer than a submit can take.
+pub fn poll_open_orders(book: &Arc<Mutex<OrderBook>>, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    let now = Utc::now();
+    let (to_poll, to_find): (Vec<(String, String)>, Vec<String>) = {
+        let mut guard = book.lock().unwrap();
+        if guard.last_poll.is_some_and(|last| last + POLL_INTERVAL > now) {
+            return;
+        }
+        guard.last_poll = Some(now);
+        let to_poll = guard
+            .open_orders()
+            .filter(|order| !matches!(order.status, OrderStatus::Pending | OrderStatus::ReplacePending | OrderStatus::Unconfirmed(_)))
+            .filter_map(|order| Some((order.client_id.clone(), order.server_id.clone()?)))
+            .collect();
+        let to_find = guard
+            .open_orders()
+            .filter(|order| match order.status {
+                OrderStatus::Unconfirmed(_) => true,
+                OrderStatus::Pending => order.created_at + ACK_TIMEOUT < now,
+                _ => false,
+            })
+            .map(|order| order.client_id.clone())
+            .collect();
+        (to_poll, to_find)
+    };
+
+    for (client_id, server_id) in to_poll {
+        let book = Arc::clone(book);
+        let ctx = ctx.clone();
+        backend.fetch_order(&server_id, Box::new(move |result| {
+            match result {
+                Ok(ack) => book.lock().unwrap().apply_ack(&client_id, ack),
+                Err(e) => log::warn!("Polling order {client_id} failed: {e}"),
+            }
+            ctx.request_repaint();
+        }));
+    }
+
+    for client_id in to_find {
+        let book = Arc::clone(book);
+        let ctx = ctx.clone();
+        let lookup = client_id.clone();
+        backend.find_order(&lookup, Box::new(move |result| {
+            let mut book = book.lock().unwrap();
+            // the submit's own answer may have turned up in the meantime
+            if !book.get(&client_id).is_some_and(|order| matches!(order.status, OrderStatus::Pending | OrderStatus::Unconfirmed(_))) {
+                return;
+            }
+            match result {
+                Ok(ack) => {
+                    log::info!("Order {client_id} confirmed by the server");
+                    book.apply_ack(&client_id, ack);
+                }
+                // only now do we know it never got there
+                Err(ClientError::Http { status: 404, .. }) => {
+                    log::warn!("Order {client_id} never reached the server");
+                    book.set_status(&client_id, OrderStatus::Rejected("never reached the server".to_owned()));
+                }
+                Err(e) => log::warn!("Looking up order {client_id} failed: {e}"),
+            }
+            ctx.request_repaint();
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockBackend;
+
+    struct Setup {
+        book: Arc<Mutex<OrderBook>>,
+        backend: MockBackend,
+        ctx: egui::Context,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let backend = MockBackend::default();
+            backend.fill_manually();
+            Self { book: Arc::default(), backend, ctx: egui::Context::default() }
+        }
+
+        fn limit(&self, side: Side, qty: u32, price: i64) -> OrderSpec {
+            let price = Price::from_cents(price * 100);
+            OrderSpec {
+                symbol: "AAPL".to_owned(),
+                side,
+                qty,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::Gtc,
+                limit_price: Some(price),
+                stop_price: None,
+                price,
+                leg: None,
+            }
+        }
+
+        /// A buy of `qty` at 100, taking profit at 110 and stopping out at 95.
+        fn bracket(&self, qty: u32) -> String {
+            let bracket = BracketSpec { take_profit: Price::from_cents(11_000), stop_loss: Price::from_cents(9_500) };
+            submit_bracket(&self.book, &self.backend, &self.ctx, self.limit(Side::Buy, qty, 100), bracket)
+        }
+
+        /// What the app does every frame, without waiting for the poll interval.
+        fn step(&self) {
+            self.book.lock().unwrap().last_poll = None;
+            poll_open_orders(&self.book, &self.backend, &self.ctx);
+            manage_brackets(&self.book, &self.backend, &self.ctx);
+        }
+
+        fn order(&self, client_id: &str) -> Order {
+            self.book.lock().unwrap().get(client_id).cloned().unwrap()
+        }
+
+        fn state(&self) -> Bracket {
+            self.book.lock().unwrap().brackets[0].clone()
+        }
+
+        /// Take-profit and stop-loss as (qty, filled, status).
+        fn exits(&self) -> [(u32, u32, OrderStatus); 2] {
+            let bracket = self.state();
+            [bracket.take_profit_order, bracket.stop_loss_order].map(|client_id| {
+                let order = self.order(&client_id.unwrap());
+                (order.qty, order.filled_qty, order.status)
+            })
+        }
+    }
+
+    #[test]
+    fn submit_records_the_server_id() {
+        let setup = Setup::new();
+        let client_id = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        let order = setup.order(&client_id);
+        assert_eq!(order.status, OrderStatus::Accepted);
+        assert_eq!(order.server_id.as_deref(), Some("mock-1"));
+
+        setup.backend.fill(&client_id, 4);
+        setup.step();
+        assert_eq!(setup.order(&client_id).status, OrderStatus::PartiallyFilled);
+        let fills = setup.book.lock().unwrap().fills().to_vec();
+        assert_eq!((fills.len(), fills[0].qty, fills[0].price), (1, 4, Price::from_cents(10_000)));
+    }
+
+    #[test]
+    fn a_lost_answer_is_looked_up_not_rejected() {
+        let setup = Setup::new();
+        setup.backend.fail_with(Some(ClientError::Timeout { elapsed_secs: 12.0 }));
+        let lost = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        assert!(matches!(setup.order(&lost).status, OrderStatus::Unconfirmed(_)));
+        assert!(setup.order(&lost).status.is_open());
+
+        // the mock never recorded it, so the lookup says it never got there
+        setup.backend.fail_with(None);
+        setup.step();
+        assert!(matches!(setup.order(&lost).status, OrderStatus::Rejected(_)));
+
+        // this one got there, only the answer went missing
+        let arrived = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        {
+            let mut book = setup.book.lock().unwrap();
+            book.set_status(&arrived, OrderStatus::Unconfirmed("request timed out".to_owned()));
+            book.get_mut(&arrived).unwrap().server_id = None;
+        }
+        setup.step();
+        let order = setup.order(&arrived);
+        assert_eq!(order.status, OrderStatus::Accepted);
+        assert!(order.server_id.is_some());
+    }
+
+    #[test]
+    fn a_long_pending_order_is_looked_up() {
+        let setup = Setup::new();
+        let fresh = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        let stale = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        {
+            // both answers lost, one of them long ago
+            let mut book = setup.book.lock().unwrap();
+            for client_id in [&fresh, &stale] {
+                let order = book.get_mut(client_id).unwrap();
+                order.status = OrderStatus::Pending;
+                order.server_id = None;
+            }
+            book.get_mut(&stale).unwrap().created_at -= chrono::Duration::minutes(5);
+        }
+        setup.step();
+        assert_eq!(setup.order(&fresh).status, OrderStatus::Pending);
+        assert_eq!(setup.order(&stale).status, OrderStatus::Accepted);
+        assert!(setup.order(&stale).server_id.is_some());
+    }
+
+    #[test]
+    fn client_ids_differ_between_books() {
+        let spec = Setup::new().limit(Side::Buy, 10, 100);
+        let (mut first, mut second) = (OrderBook::default(), OrderBook::default());
+        let ids = [first.new_order(&spec), first.new_order(&spec), second.new_order(&spec)].map(|order| order.client_id);
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[0], ids[2]);
+        assert!(ids[1].ends_with("-000002"), "{ids:?}");
+    }
+
+    #[test]
+    fn a_failed_cancel_keeps_the_status() {
+        let setup = Setup::new();
+        let client_id = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        setup.backend.fill(&client_id, 4);
+        setup.step();
+
+        setup.backend.fail_with(Some(ClientError::Network("connection refused".to_owned())));
+        cancel(&setup.book, &setup.backend, &setup.ctx, &client_id);
+        assert_eq!(setup.order(&client_id).status, OrderStatus::PartiallyFilled);
+
+        setup.backend.fail_with(None);
+        cancel(&setup.book, &setup.backend, &setup.ctx, &client_id);
+        assert_eq!(setup.order(&client_id).status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn bracket_exits_follow_the_position() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Entry);
+        assert_eq!(setup.state().levels().len(), 2);
+
+        // protected from the first partial fill on
+        setup.backend.fill(&entry, 40);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Exits);
+        assert_eq!(setup.exits(), [(40, 0, OrderStatus::Accepted), (40, 0, OrderStatus::Accepted)]);
+
+        // and grown as the entry fills
+        setup.backend.fill(&entry, 60);
+        setup.step();
+        setup.step();
+        assert_eq!(setup.exits(), [(100, 0, OrderStatus::Accepted), (100, 0, OrderStatus::Accepted)]);
+
+        // a partial take-profit shrinks the stop rather than cancelling it
+        let take_profit = setup.state().take_profit_order.unwrap();
+        setup.backend.fill(&take_profit, 30);
+        setup.step();
+        setup.step();
+        assert_eq!(setup.exits(), [(100, 30, OrderStatus::PartiallyFilled), (70, 0, OrderStatus::Accepted)]);
+
+        // the rest of it closes the position, and the stop goes
+        setup.backend.fill(&take_profit, 70);
+        setup.step();
+        assert_eq!(setup.exits(), [(100, 100, OrderStatus::Filled), (70, 0, OrderStatus::Cancelled)]);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Closed);
+    }
+
+    #[test]
+    fn a_failed_take_profit_leaves_the_stop() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        setup.backend.fill(&entry, 100);
+        setup.step();
+        setup.step();
+
+        let take_profit = setup.state().take_profit_order.unwrap();
+        cancel(&setup.book, &setup.backend, &setup.ctx, &take_profit);
+        setup.step();
+        assert_eq!(setup.exits(), [(100, 0, OrderStatus::Cancelled), (100, 0, OrderStatus::Accepted)]);
+        assert_eq!(setup.state().state, BracketState::Exits);
+    }
+
+    #[test]
+    fn closing_early_cancels_the_rest_of_the_entry() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        setup.backend.fill(&entry, 50);
+        setup.step();
+
+        let stop_loss = setup.state().stop_loss_order.unwrap();
+        setup.backend.fill(&stop_loss, 50);
+        setup.step();
+        assert_eq!(setup.exits(), [(50, 0, OrderStatus::Cancelled), (50, 50, OrderStatus::Filled)]);
+        assert_eq!(setup.order(&entry).status, OrderStatus::Cancelled);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Closed);
+    }
+
+    #[test]
+    fn clearing_history_keeps_a_working_bracket() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        setup.backend.fill(&entry, 100);
+        setup.step();
+        setup.step();
+        let done = submit(&setup.book, &setup.backend, &setup.ctx, setup.limit(Side::Buy, 10, 100));
+        setup.backend.fill(&done, 10);
+        setup.step();
+
+        setup.book.lock().unwrap().clear_history();
+        assert!(setup.book.lock().unwrap().get(&done).is_none());
+        assert_eq!(setup.order(&entry).status, OrderStatus::Filled);
+
+        let take_profit = setup.state().take_profit_order.unwrap();
+        setup.backend.fill(&take_profit, 100);
+        setup.step();
+        assert_eq!(setup.exits(), [(100, 100, OrderStatus::Filled), (100, 0, OrderStatus::Cancelled)]);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Closed);
+
+        setup.book.lock().unwrap().clear_history();
+        assert!(setup.book.lock().unwrap().orders().is_empty());
+    }
+
+    #[test]
+    fn exits_carry_on_without_their_entry() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        setup.backend.fill(&entry, 100);
+        setup.step();
+        setup.step();
+        // should it go missing anyway
+        setup.book.lock().unwrap().orders.retain(|order| order.client_id != entry);
+
+        let stop_loss = setup.state().stop_loss_order.unwrap();
+        setup.backend.fill(&stop_loss, 40);
+        setup.step();
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Exits);
+        assert_eq!(setup.exits(), [(60, 0, OrderStatus::Accepted), (100, 40, OrderStatus::PartiallyFilled)]);
+
+        setup.backend.fill(&stop_loss, 60);
+        setup.step();
+        assert_eq!(setup.exits(), [(60, 0, OrderStatus::Cancelled), (100, 100, OrderStatus::Filled)]);
+        setup.step();
+        assert_eq!(setup.state().state, BracketState::Closed);
+    }
+
+    #[test]
+    fn an_unfilled_entry_closes_the_bracket() {
+        let setup = Setup::new();
+        let entry = setup.bracket(100);
+        cancel(&setup.book, &setup.backend, &setup.ctx, &entry);
+        setup.step();
+        let bracket = setup.state();
+        assert_eq!(bracket.state, BracketState::Closed);
+        assert_eq!(bracket.take_profit_order, None);
+    }
+}
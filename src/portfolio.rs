@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, Utc};
+
+use crate::{
+    orders::{Fill, Side},
+    price::Price,
+};
+
+/// Latest price for a symbol and the one today's change is measured against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mark {
+    pub last: f64,
+    // previous session's close, or the session open if that's all we have
+    pub reference: f64,
+}
+
+/// Net holding in one symbol, at average cost. Negative quantities are shorts.
+#[derive(Clone, Debug, Default)]
+pub struct Position {
+    pub symbol: String,
+    pub qty: i64,
+    // what the open quantity cost, signed like `qty`; kept whole so averaging can't drift
+    cost: Price,
+    pub realized_pnl: Price,
+    pub mark: Option<Mark>,
+    // today's fills, for the daily P&L
+    session: Option<NaiveDate>,
+    session_qty: i64,
+    session_cash: Price,
+}
+
+impl Position {
+    fn new(symbol: &str) -> Self {
+        Self { symbol: symbol.to_owned(), ..Default::default() }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.qty == 0
+    }
+
+    pub fn avg_cost(&self) -> Price {
+        self.cost.per(self.qty)
+    }
+
+    pub fn last_price(&self) -> Option<Price> {
+        self.mark.map(|mark| Price::from_f64(mark.last))
+    }
+
+    /// Marked at the last close, or at cost until we have a price.
+    pub fn market_value(&self) -> Price {
+        self.last_price().map_or(self.cost, |last| last.times(self.qty))
+    }
+
+    pub fn cost_basis(&self) -> Price {
+        self.cost
+    }
+
+    pub fn unrealized_pnl(&self) -> Price {
+        self.market_value() - self.cost
+    }
+
+    fn session_trades(&self) -> (i64, Price) {
+        match self.session {
+            Some(session) if session == Utc::now().date_naive() => (self.session_qty, self.session_cash),
+            _ => (0, Price::ZERO),
+        }
+    }
+
+    /// Change in value since the previous close: what we held then moved from the reference,
+    /// what we traded today moved from its fill price.
+    pub fn daily_pnl(&self) -> Price {
+        let Some(mark) = self.mark else {
+            return Price::ZERO;
+        };
+        let (traded_qty, cash) = self.session_trades();
+        let start_qty = self.qty - traded_qty;
+        Price::from_f64(mark.last).times(self.qty) - Price::from_f64(mark.reference).times(start_qty) + cash
+    }
+
+    /// What the position was worth at the previous close.
+    fn reference_value(&self) -> Price {
+        let (traded_qty, _) = self.session_trades();
+        self.mark.map_or(Price::ZERO, |mark| Price::from_f64(mark.reference).times(self.qty - traded_qty))
+    }
+
+    fn apply(&mut self, fill: &Fill) {
+        let signed_qty = match fill.side {
+            Side::Buy => fill.qty as i64,
+            Side::Sell => -(fill.qty as i64),
+        };
+
+        if self.qty == 0 || self.qty.signum() == signed_qty.signum() {
+            // adding to the position
+            self.cost += fill.price.times(signed_qty);
+        } else {
+            // reducing it, possibly through zero into the other side
+            let closed = self.qty.abs().min(signed_qty.abs());
+            let closed_cost = self.cost.times(closed).per(self.qty.abs());
+            self.realized_pnl += fill.price.times(closed * self.qty.signum()) - closed_cost;
+            self.cost -= closed_cost;
+            if signed_qty.abs() > self.qty.abs() {
+                self.cost = fill.price.times(self.qty + signed_qty);
+            }
+        }
+        self.qty += signed_qty;
+
+        let day = fill.at.date_naive();
+        if self.session != Some(day) {
+            self.session = Some(day);
+            self.session_qty = 0;
+            self.session_cash = Price::ZERO;
+        }
+        self.session_qty += signed_qty;
+        self.session_cash -= fill.price.times(signed_qty);
+    }
+}
+
+/// Positions built up from the order book's fills, marked against the watched stocks.
+#[derive(Default)]
+pub struct Portfolio {
+    positions: BTreeMap<String, Position>,
+    // fills already applied, so only new ones are processed
+    applied: usize,
+}
+
+impl Portfolio {
+    /// Applies fills we haven't seen yet. Rebuilds from scratch if the list got shorter.
+    pub fn sync(&mut self, fills: &[Fill]) {
+        if fills.len() < self.applied {
+            self.positions.clear();
+            self.applied = 0;
+        }
+        for fill in &fills[self.applied..] {
+            self.positions
+                .entry(fill.symbol.clone())
+                .or_insert_with(|| Position::new(&fill.symbol))
+                .apply(fill);
+        }
+        self.applied = fills.len();
+    }
+
+    pub fn set_mark(&mut self, symbol: &str, mark: Option<Mark>) {
+        if let Some(position) = self.positions.get_mut(symbol) {
+            position.mark = mark;
+        }
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    /// Every symbol we've traded, flat ones included for their realized P&L.
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn open_positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions().filter(|position| !position.is_flat())
+    }
+
+    pub fn total_value(&self) -> Price {
+        self.positions().map(Position::market_value).sum()
+    }
+
+    pub fn realized_pnl(&self) -> Price {
+        self.positions().map(|position| position.realized_pnl).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> Price {
+        self.positions().map(Position::unrealized_pnl).sum()
+    }
+
+    pub fn daily_pnl(&self) -> Price {
+        self.positions().map(Position::daily_pnl).sum()
+    }
+
+    /// Daily P&L relative to what the book was worth at the previous close, in percent.
+    pub fn daily_pnl_percent(&self) -> f64 {
+        let reference: Price = self.positions().map(|position| position.reference_value().abs()).sum();
+        if reference == Price::ZERO { 0.0 } else { self.daily_pnl().to_f64() / reference.to_f64() * 100.0 }
+    }
+}
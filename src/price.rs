@@ -0,0 +1,299 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+};
+
+/// Fixed-point money amount in ten-thousandths of a dollar, for prices, totals and P&L.
+/// Typed prices are parsed exactly, so what's shown, checked and submitted always agree.
+/// Serialized as a plain number, which keeps the wire format and older saved state working.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(from = "f64", into = "f64")]
+pub struct Price(i64);
+
+impl Price {
+    pub const DECIMALS: u32 = 4;
+    const SCALE: i64 = 10_i64.pow(Self::DECIMALS);
+    pub const ZERO: Price = Price(0);
+
+    /// Whole cents, e.g. `from_cents(17550)` is $175.50.
+    pub const fn from_cents(cents: i64) -> Self {
+        Self(cents * (Self::SCALE / 100))
+    }
+
+    /// Rounds to the nearest unit. For numbers that are already floats, like market data or
+    /// a server's average fill price.
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * Self::SCALE as f64).round() as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+
+    /// Parses a decimal like `175.5` or `0.0025` without going through a float.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim().trim_start_matches('$');
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() && fraction.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+            return Err(format!("'{text}' is not a number"));
+        }
+        if fraction.len() > Self::DECIMALS as usize {
+            return Err(format!("'{text}' has more than {} decimals", Self::DECIMALS));
+        }
+
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| format!("'{text}' is too large"))? };
+        let fraction: i64 = format!("{fraction:0<width$}", width = Self::DECIMALS as usize).parse().unwrap_or(0);
+        let units = whole
+            .checked_mul(Self::SCALE)
+            .and_then(|units| units.checked_add(fraction))
+            .ok_or_else(|| format!("'{text}' is too large"))?;
+        Ok(Self(if negative { -units } else { units }))
+    }
+
+    /// Price × quantity, exactly, or `None` when it doesn't fit. For anything typed in.
+    pub fn checked_times(self, qty: i64) -> Option<Self> {
+        self.0.checked_mul(qty).map(Self)
+    }
+
+    /// Price × quantity, pinned to the largest amount there is rather than wrapping around.
+    pub fn times(self, qty: i64) -> Self {
+        Self(self.0.saturating_mul(qty))
+    }
+
+    /// Amount ÷ quantity, rounded half away from zero. For average prices.
+    pub fn per(self, qty: i64) -> Self {
+        if qty == 0 {
+            return Self::ZERO;
+        }
+        let (amount, qty) = (self.0 as i128, qty as i128);
+        let magnitude = (amount.abs() * 2 + qty.abs()) / (qty.abs() * 2);
+        let sign = if (amount < 0) != (qty < 0) { -1 } else { 1 };
+        Self((magnitude * sign) as i64)
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Whether this is a whole number of `tick`s.
+    pub fn is_multiple_of(self, tick: Price) -> bool {
+        tick.0 <= 0 || self.0 % tick.0 == 0
+    }
+
+    /// Nearest whole number of `tick`s.
+    pub fn round_to(self, tick: Price) -> Self {
+        if tick.0 <= 0 {
+            return self;
+        }
+        let ticks = (self.0 + tick.0 / 2).div_euclid(tick.0);
+        Self(ticks * tick.0)
+    }
+}
+
+impl From<f64> for Price {
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl From<Price> for f64 {
+    fn from(price: Price) -> Self {
+        price.to_f64()
+    }
+}
+
+/// At least two decimals, more only when they're not zero: `175.50`, `0.0025`.
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        let fraction = format!("{:0width$}", units % scale, width = Self::DECIMALS as usize);
+        let fraction = fraction.trim_end_matches('0');
+        write!(f, "{sign}{}.{fraction:0<2}", units / scale)
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+
+    fn add(self, other: Price) -> Price {
+        Price(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Price {
+    fn add_assign(&mut self, other: Price) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    fn sub(self, other: Price) -> Price {
+        Price(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Price {
+    fn sub_assign(&mut self, other: Price) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Price {
+    type Output = Price;
+
+    fn neg(self) -> Price {
+        Price(-self.0)
+    }
+}
+
+impl Sum for Price {
+    fn sum<I: Iterator<Item = Price>>(iter: I) -> Price {
+        iter.fold(Price::ZERO, Add::add)
+    }
+}
+
+/// How a symbol trades: prices in whole ticks, quantities in whole lots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct InstrumentSpec {
+    pub tick_size: Price,
+    pub lot_size: u32,
+}
+
+impl Default for InstrumentSpec {
+    fn default() -> Self {
+        Self { tick_size: Price::from_cents(1), lot_size: 1 }
+    }
+}
+
+impl InstrumentSpec {
+    pub fn check_price(&self, price: Price, name: &str) -> Result<Price, String> {
+        if !price.is_positive() {
+            return Err(format!("{name} must be above 0"));
+        }
+        if !price.is_multiple_of(self.tick_size) {
+            return Err(format!("{name} ${price} is not a multiple of the ${} tick", self.tick_size));
+        }
+        Ok(price)
+    }
+
+    pub fn check_qty(&self, qty: u32) -> Result<u32, String> {
+        if qty == 0 {
+            return Err("Quantity must be above 0".to_owned());
+        }
+        if self.lot_size > 1 && !qty.is_multiple_of(self.lot_size) {
+            return Err(format!("Quantity {qty} is not a multiple of the {}-share lot", self.lot_size));
+        }
+        Ok(qty)
+    }
+}
+
+/// Tick and lot sizes, per symbol with a default for everything else.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Instruments {
+    pub default: InstrumentSpec,
+    pub overrides: BTreeMap<String, InstrumentSpec>,
+}
+
+impl Instruments {
+    /// The override for `symbol` in any case, they're kept uppercase.
+    pub fn get(&self, symbol: &str) -> InstrumentSpec {
+        self.overrides.get(&symbol.trim().to_uppercase()).copied().unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exactly() {
+        assert_eq!(Price::parse("175.5"), Ok(Price::from_cents(17550)));
+        assert_eq!(Price::parse(" $0.0025 "), Ok(Price(25)));
+        assert_eq!(Price::parse("-3"), Ok(Price::from_cents(-300)));
+        assert_eq!(Price::parse(".5"), Ok(Price::from_cents(50)));
+        assert_eq!(Price::parse("12."), Ok(Price::from_cents(1200)));
+    }
+
+    #[test]
+    fn rejects_bad_prices() {
+        for text in ["", ".", "abc", "1.2.3", "1e5", "--1", "0.00001", "99999999999999999999"] {
+            assert!(Price::parse(text).is_err(), "{text:?} parsed");
+        }
+    }
+
+    #[test]
+    fn displays_at_least_two_decimals() {
+        assert_eq!(Price::from_cents(17550).to_string(), "175.50");
+        assert_eq!(Price(25).to_string(), "0.0025");
+        assert_eq!(Price::from_cents(-5).to_string(), "-0.05");
+    }
+
+    #[test]
+    fn rounds_to_the_nearest_tick() {
+        let tick = Price::from_cents(5);
+        assert_eq!(Price::parse("1.02").unwrap().round_to(tick), Price::from_cents(100));
+        assert_eq!(Price::parse("1.025").unwrap().round_to(tick), Price::from_cents(105));
+        assert_eq!(Price::parse("1.03").unwrap().round_to(tick), Price::from_cents(105));
+        assert_eq!(Price::parse("-1.03").unwrap().round_to(tick), Price::from_cents(-105));
+        assert_eq!(Price::parse("1.03").unwrap().round_to(Price::ZERO), Price::parse("1.03").unwrap());
+        assert!(Price::from_cents(105).is_multiple_of(tick));
+        assert!(!Price::from_cents(103).is_multiple_of(tick));
+    }
+
+    #[test]
+    fn times_does_not_wrap() {
+        let price = Price::parse("1000000").unwrap();
+        assert_eq!(price.checked_times(1_000_000_000), None);
+        assert_eq!(price.times(1_000_000_000), Price(i64::MAX));
+        assert_eq!(price.times(-1_000_000_000), Price(i64::MIN));
+        assert_eq!(Price::from_cents(17550).checked_times(100), Some(Price::from_cents(1_755_000)));
+    }
+
+    #[test]
+    fn per_rounds_half_away_from_zero() {
+        assert_eq!(Price::from_cents(1000).per(3), Price(33333));
+        assert_eq!(Price(5).per(2), Price(3));
+        assert_eq!(Price(-5).per(2), Price(-3));
+        assert_eq!(Price(5).per(0), Price::ZERO);
+    }
+
+    #[test]
+    fn overrides_match_any_case() {
+        let spec = InstrumentSpec { tick_size: Price::from_cents(5), lot_size: 100 };
+        let instruments = Instruments { overrides: BTreeMap::from([("AAPL".to_owned(), spec)]), ..Default::default() };
+        assert_eq!(instruments.get("aapl"), spec);
+        assert_eq!(instruments.get("AAPL"), spec);
+        assert_eq!(instruments.get("MSFT"), InstrumentSpec::default());
+    }
+
+    #[test]
+    fn checks_ticks_and_lots() {
+        let spec = InstrumentSpec { tick_size: Price::from_cents(5), lot_size: 100 };
+        assert!(spec.check_price(Price::from_cents(105), "Limit").is_ok());
+        assert!(spec.check_price(Price::from_cents(103), "Limit").is_err());
+        assert!(spec.check_price(Price::ZERO, "Limit").is_err());
+        assert_eq!(spec.check_qty(200), Ok(200));
+        assert!(spec.check_qty(150).is_err());
+        assert!(spec.check_qty(0).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    orders::{OrderSpec, Side},
+    price::Price,
+};
+
+/// Pre-trade limits, checked before an order gets to its confirmation dialog. A limit of 0
+/// switches that check off.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RiskLimits {
+    pub enabled: bool,
+    /// Largest qty × price for a single order.
+    pub max_order_notional: Price,
+    /// Largest position (long or short) an order may leave us with in one symbol.
+    pub max_position: u32,
+    /// How far a limit or stop price may be from the last price, in percent.
+    pub price_band_percent: f64,
+    /// Once the day's loss reaches this, only orders that reduce a position go through.
+    pub daily_loss_limit: Price,
+    /// Comma separated, matched case-insensitively.
+    pub restricted_symbols: String,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_order_notional: Price::from_cents(10_000_000),
+            max_position: 10_000,
+            price_band_percent: 10.0,
+            daily_loss_limit: Price::from_cents(500_000),
+            restricted_symbols: String::new(),
+        }
+    }
+}
+
+/// Where we stand in the order's symbol.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Exposure {
+    /// Signed, negative is short.
+    pub position: i64,
+    pub last_price: Option<Price>,
+    /// The whole portfolio's, not just this symbol's.
+    pub daily_pnl: Price,
+}
+
+impl RiskLimits {
+    pub fn is_restricted(&self, symbol: &str) -> bool {
+        self.restricted_symbols
+            .split(',')
+            .map(str::trim)
+            .any(|restricted| !restricted.is_empty() && restricted.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Every limit the order breaks, in words. Empty means it can go.
+    pub fn check(&self, spec: &OrderSpec, exposure: &Exposure) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if !self.enabled {
+            return reasons;
+        }
+
+        if self.is_restricted(&spec.symbol) {
+            reasons.push(format!("{} is on the restricted list", spec.symbol));
+        }
+
+        match spec.price.checked_times(spec.qty as i64) {
+            Some(notional) if self.max_order_notional.is_positive() && notional > self.max_order_notional => {
+                reasons.push(format!("Order value ${notional} is over the ${} limit", self.max_order_notional));
+            }
+            Some(_) => {}
+            None => reasons.push("Order value is too large".to_owned()),
+        }
+
+        let signed_qty = match spec.side {
+            Side::Buy => spec.qty as i64,
+            Side::Sell => -(spec.qty as i64),
+        };
+        let new_position = exposure.position + signed_qty;
+        if self.max_position > 0 && new_position.unsigned_abs() > self.max_position as u64 {
+            reasons.push(format!(
+                "Position would be {new_position} shares, the limit is {} either way",
+                self.max_position
+            ));
+        }
+
+        let last_price = exposure.last_price.filter(|last| last.is_positive() && self.price_band_percent > 0.0);
+        if let Some(last) = last_price {
+            for price in [spec.limit_price, spec.stop_price].into_iter().flatten() {
+                let distance = (price - last).abs().to_f64() / last.to_f64() * 100.0;
+                if distance > self.price_band_percent {
+                    reasons.push(format!(
+                        "${price} is {distance:.1}% away from the last price ${last}, the band is {:.1}%",
+                        self.price_band_percent
+                    ));
+                }
+            }
+        }
+
+        let increases = new_position.unsigned_abs() > exposure.position.unsigned_abs();
+        if self.daily_loss_limit.is_positive() && exposure.daily_pnl <= -self.daily_loss_limit && increases {
+            reasons.push(format!(
+                "Daily loss ${} has hit the ${} limit, only reducing orders are allowed",
+                -exposure.daily_pnl,
+                self.daily_loss_limit
+            ));
+        }
+
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::{OrderType, TimeInForce};
+
+    fn limit(side: Side, qty: u32, price: &str) -> OrderSpec {
+        let price = Price::parse(price).unwrap();
+        OrderSpec {
+            symbol: "AAPL".to_owned(),
+            side,
+            qty,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Day,
+            limit_price: Some(price),
+            stop_price: None,
+            price,
+            leg: None,
+        }
+    }
+
+    fn exposure(position: i64, last: &str) -> Exposure {
+        Exposure { position, last_price: Some(Price::parse(last).unwrap()), daily_pnl: Price::ZERO }
+    }
+
+    #[test]
+    fn passes_an_ordinary_order() {
+        let limits = RiskLimits::default();
+        assert!(limits.check(&limit(Side::Buy, 100, "100"), &exposure(0, "100")).is_empty());
+    }
+
+    #[test]
+    fn stops_large_orders_and_positions() {
+        let limits = RiskLimits::default();
+        // $200,000 against the $100,000 default
+        assert_eq!(limits.check(&limit(Side::Buy, 2000, "100"), &exposure(0, "100")).len(), 1);
+        let reasons = limits.check(&limit(Side::Sell, 500, "100"), &exposure(-9_800, "100"));
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].contains("-10300"), "{reasons:?}");
+    }
+
+    #[test]
+    fn an_overflowing_notional_is_not_waved_through() {
+        let limits = RiskLimits { max_position: 0, price_band_percent: 0.0, ..Default::default() };
+        let reasons = limits.check(&limit(Side::Buy, 1_000_000_000, "1000000"), &exposure(0, "1000000"));
+        assert_eq!(reasons, vec!["Order value is too large".to_owned()]);
+    }
+
+    #[test]
+    fn keeps_prices_near_the_last_one() {
+        let limits = RiskLimits::default();
+        assert!(limits.check(&limit(Side::Buy, 10, "109"), &exposure(0, "100")).is_empty());
+        assert_eq!(limits.check(&limit(Side::Buy, 10, "111"), &exposure(0, "100")).len(), 1);
+    }
+
+    #[test]
+    fn only_reduces_after_the_daily_loss_limit() {
+        let limits = RiskLimits::default();
+        let lost = Exposure { daily_pnl: -limits.daily_loss_limit, ..exposure(100, "100") };
+        assert_eq!(limits.check(&limit(Side::Buy, 10, "100"), &lost).len(), 1);
+        assert!(limits.check(&limit(Side::Sell, 10, "100"), &lost).is_empty());
+    }
+
+    #[test]
+    fn restricted_symbols_ignore_case_and_spaces() {
+        let limits = RiskLimits { restricted_symbols: "msft, aapl".to_owned(), ..Default::default() };
+        assert!(limits.is_restricted("AAPL"));
+        assert!(!limits.is_restricted("TSLA"));
+        assert_eq!(limits.check(&limit(Side::Buy, 10, "100"), &exposure(0, "100")).len(), 1);
+        let off = RiskLimits { enabled: false, ..limits };
+        assert!(off.check(&limit(Side::Buy, 10, "100"), &exposure(0, "100")).is_empty());
+    }
+}
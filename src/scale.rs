@@ -0,0 +1,81 @@
+use crate::series::Candle;
+
+/// What the price axis shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ScaleMode {
+    #[default]
+    Price,
+    /// Equal distances are equal percentage moves.
+    Log,
+    /// Change since the first bar in view.
+    Percent,
+}
+
+impl ScaleMode {
+    pub const ALL: [ScaleMode; 3] = [ScaleMode::Price, ScaleMode::Log, ScaleMode::Percent];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScaleMode::Price => "$",
+            ScaleMode::Log => "Log",
+            ScaleMode::Percent => "%",
+        }
+    }
+
+    /// A price axis label for a plot `y`: the percentage itself, or the price it stands for.
+    /// Neither needs the base, so the axis can be labelled before the view is known.
+    pub fn format(&self, y: f64) -> String {
+        match self {
+            ScaleMode::Price => format!("{y:.2}"),
+            ScaleMode::Log => format!("{:.2}", 10f64.powf(y)),
+            ScaleMode::Percent => format!("{y:+.2}%"),
+        }
+    }
+}
+
+/// Maps prices to where the plot draws them and back. `base` is the price at the first bar in
+/// view, which is 0% and what comparisons are scaled to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct YScale {
+    pub mode: ScaleMode,
+    pub base: f64,
+}
+
+impl Default for YScale {
+    fn default() -> Self {
+        Self { mode: ScaleMode::Price, base: 1.0 }
+    }
+}
+
+impl YScale {
+    pub fn new(mode: ScaleMode, base: f64) -> Self {
+        // a zero or negative base would make every percentage infinite
+        Self { mode, base: if base > 0.0 { base } else { 1.0 } }
+    }
+
+    pub fn to_plot(&self, price: f64) -> f64 {
+        match self.mode {
+            ScaleMode::Price => price,
+            ScaleMode::Log => price.max(f64::MIN_POSITIVE).log10(),
+            ScaleMode::Percent => (price / self.base - 1.0) * 100.0,
+        }
+    }
+
+    pub fn to_price(&self, y: f64) -> f64 {
+        match self.mode {
+            ScaleMode::Price => y,
+            ScaleMode::Log => 10f64.powf(y),
+            ScaleMode::Percent => self.base * (1.0 + y / 100.0),
+        }
+    }
+
+    pub fn candle(&self, candle: &Candle) -> Candle {
+        Candle {
+            open: self.to_plot(candle.open),
+            high: self.to_plot(candle.high),
+            low: self.to_plot(candle.low),
+            close: self.to_plot(candle.close),
+            ..*candle
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use chrono::{DateTime, Datelike, Days, NaiveTime, TimeZone, Utc};
+use rusty_trading_model::structs::Point;
+
+/// One OHLCV bar, ready for plotting. `x` is the bar's timestamp in epoch milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candle {
+    pub x: f64,
+    pub time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl From<&Point> for Candle {
+    fn from(point: &Point) -> Self {
+        Self {
+            x: point.timestamp.timestamp_millis() as f64,
+            time: point.timestamp,
+            open: point.open,
+            high: point.high,
+            low: point.low,
+            close: point.close,
+            volume: point.volume as f64,
+        }
+    }
+}
+
+/// Bar size the chart shows. Finer data from the server is aggregated up to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+pub enum Timeframe {
+    #[default]
+    M1,
+    M5,
+    M15,
+    H1,
+    D1,
+    W1,
+}
+
+impl Timeframe {
+    pub const ALL: [Timeframe; 6] = [Timeframe::M1, Timeframe::M5, Timeframe::M15, Timeframe::H1, Timeframe::D1, Timeframe::W1];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Timeframe::M1 => "1m",
+            Timeframe::M5 => "5m",
+            Timeframe::M15 => "15m",
+            Timeframe::H1 => "1h",
+            Timeframe::D1 => "1D",
+            Timeframe::W1 => "1W",
+        }
+    }
+
+    /// Length of one bar in milliseconds.
+    pub fn millis(&self) -> i64 {
+        const MINUTE: i64 = 60_000;
+        match self {
+            Timeframe::M1 => MINUTE,
+            Timeframe::M5 => 5 * MINUTE,
+            Timeframe::M15 => 15 * MINUTE,
+            Timeframe::H1 => 60 * MINUTE,
+            Timeframe::D1 => 24 * 60 * MINUTE,
+            Timeframe::W1 => 7 * 24 * 60 * MINUTE,
+        }
+    }
+
+    pub fn is_intraday(&self) -> bool {
+        self.millis() < Timeframe::D1.millis()
+    }
+
+    /// The finest timeframe `candles` can be shown at, going by the smallest gap between them.
+    pub fn of(candles: &[Candle]) -> Timeframe {
+        let step = candles
+            .windows(2)
+            .map(|pair| pair[1].x - pair[0].x)
+            .filter(|&step| step > 0.0)
+            .fold(f64::INFINITY, f64::min);
+        if !step.is_finite() {
+            return Timeframe::default();
+        }
+        // a little slack for servers stamping bars a few milliseconds off
+        Timeframe::ALL
+            .into_iter()
+            .find(|timeframe| timeframe.millis() as f64 >= step * 0.99)
+            .unwrap_or(Timeframe::W1)
+    }
+
+    /// Start of the bar `time` falls in. Days are UTC days like the sessions, weeks start on
+    /// Monday.
+    pub fn bar_start(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        if *self == Timeframe::W1 {
+            let date = time.date_naive();
+            let monday = date - Days::new(date.weekday().num_days_from_monday() as u64);
+            return monday.and_time(NaiveTime::MIN).and_utc();
+        }
+        let millis = time.timestamp_millis();
+        Utc.timestamp_millis_opt(millis - millis.rem_euclid(self.millis())).single().unwrap_or(time)
+    }
+}
+
+/// Aggregates consecutive candles into `timeframe` bars. Candles already coarser than the
+/// timeframe come out as they went in.
+pub fn resample(candles: &[Candle], timeframe: Timeframe) -> Vec<Candle> {
+    let mut bars: Vec<Candle> = Vec::new();
+    for candle in candles {
+        let start = timeframe.bar_start(candle.time);
+        match bars.last_mut() {
+            Some(bar) if bar.time == start => {
+                bar.high = bar.high.max(candle.high);
+                bar.low = bar.low.min(candle.low);
+                bar.close = candle.close;
+                bar.volume += candle.volume;
+            }
+            _ => bars.push(Candle { x: start.timestamp_millis() as f64, time: start, ..*candle }),
+        }
+    }
+    bars
+}
+
+/// A buffer's candles resampled to a timeframe, redone only when either changes.
+#[derive(Default)]
+pub struct Resampled {
+    key: Option<(u64, Timeframe)>,
+    candles: Vec<Candle>,
+    finest: Timeframe,
+}
+
+impl Resampled {
+    /// The bars, and the timeframe they're really at: coarser than asked for if the data is.
+    pub fn get(&mut self, buffer: &SeriesBuffer, timeframe: Timeframe) -> (&[Candle], Timeframe) {
+        let key = Some((buffer.version(), timeframe));
+        if self.key != key {
+            self.finest = Timeframe::of(buffer.candles());
+            self.candles = resample(buffer.candles(), timeframe);
+            self.key = key;
+        }
+        (&self.candles, timeframe.max(self.finest))
+    }
+
+    /// The finest timeframe the data allows, as of the last `get`.
+    pub fn finest(&self) -> Timeframe {
+        self.finest
+    }
+}
+
+/// The points we have for a stock, sorted by timestamp, plus their render-ready candles.
+/// Updates are merged in place so polling only has to fetch what changed.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(from = "Vec<Point>", into = "Vec<Point>")]
+pub struct SeriesBuffer {
+    points: Vec<Point>,
+    candles: Vec<Candle>,
+    // bumped on every change, so derived data can tell when it's stale
+    version: u64,
+}
+
+impl From<Vec<Point>> for SeriesBuffer {
+    fn from(mut points: Vec<Point>) -> Self {
+        points.sort_by_key(|point| point.timestamp);
+        let candles = points.iter().map(Candle::from).collect();
+        Self { points, candles, version: 0 }
+    }
+}
+
+impl From<SeriesBuffer> for Vec<Point> {
+    fn from(buffer: SeriesBuffer) -> Self {
+        buffer.points
+    }
+}
+
+impl SeriesBuffer {
+    pub fn candles(&self) -> &[Candle] {
+        &self.candles
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Timestamp of the newest point, where the next incremental fetch should start.
+    pub fn last_time(&self) -> Option<DateTime<Utc>> {
+        self.points.last().map(|point| point.timestamp)
+    }
+
+    /// Merges fetched points: a point with a timestamp we already have replaces ours (that's
+    /// how the in-progress bar gets updated), newer points are appended and anything else is
+    /// inserted in order. Returns whether anything changed, a point we already have as it is
+    /// doesn't count.
+    pub fn merge(&mut self, points: impl IntoIterator<Item = Point>) -> bool {
+        let mut changed = false;
+        for point in points {
+            let candle = Candle::from(&point);
+            if self.points.last().is_none_or(|last| last.timestamp < point.timestamp) {
+                self.points.push(point);
+                self.candles.push(candle);
+            } else {
+                match self.points.binary_search_by_key(&point.timestamp, |p| p.timestamp) {
+                    Ok(index) if self.candles[index] == candle => continue,
+                    Ok(index) => {
+                        self.points[index] = point;
+                        self.candles[index] = candle;
+                    }
+                    Err(index) => {
+                        self.points.insert(index, point);
+                        self.candles.insert(index, candle);
+                    }
+                }
+            }
+            changed = true;
+        }
+        if changed {
+            self.version += 1;
+        }
+        changed
+    }
+
+    /// Moves the last bar's close to `price`, stretching its high/low as needed. Ticks older
+    /// than the last bar are dropped; new bars only come from the server.
+    pub fn apply_tick(&mut self, price: f64, timestamp: DateTime<Utc>) -> bool {
+        let Some(last) = self.points.last() else {
+            return false;
+        };
+        if timestamp < last.timestamp {
+            return false;
+        }
+        let mut point = last.clone();
+        point.close = price;
+        point.high = point.high.max(price);
+        point.low = point.low.min(price);
+        self.merge([point])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap() + chrono::Duration::minutes(minutes)
+    }
+
+    // the same JSON the server sends
+    fn point(minutes: i64, close: f64) -> Point {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": time(minutes),
+            "open": close - 1.0,
+            "high": close + 1.0,
+            "low": close - 2.0,
+            "close": close,
+            "volume": 10,
+        }))
+        .unwrap()
+    }
+
+    fn closes(buffer: &SeriesBuffer) -> Vec<f64> {
+        buffer.candles().iter().map(|candle| candle.close).collect()
+    }
+
+    #[test]
+    fn merge_appends_replaces_and_inserts() {
+        let mut buffer = SeriesBuffer::from(vec![point(2, 2.0), point(0, 0.0)]);
+        assert_eq!(closes(&buffer), [0.0, 2.0]);
+
+        assert!(buffer.merge([point(2, 2.5), point(3, 3.0)]));
+        assert_eq!(closes(&buffer), [0.0, 2.5, 3.0]);
+        assert!(buffer.merge([point(1, 1.0)]));
+        assert_eq!(closes(&buffer), [0.0, 1.0, 2.5, 3.0]);
+        assert_eq!(buffer.last_time(), Some(time(3)));
+        assert_eq!(buffer.version(), 2);
+
+        assert!(!buffer.merge([]));
+        assert_eq!(buffer.version(), 2);
+    }
+
+    #[test]
+    fn merging_what_we_have_changes_nothing() {
+        let points = || vec![point(0, 0.0), point(1, 1.0), point(2, 2.0)];
+        let mut buffer = SeriesBuffer::from(points());
+        let version = buffer.version();
+        assert!(!buffer.merge(points()));
+        assert!(!buffer.merge(points().into_iter().skip(1)));
+        assert_eq!(buffer.version(), version);
+
+        assert!(buffer.merge([point(0, 0.0), point(2, 2.5)]));
+        assert_eq!(buffer.version(), version + 1);
+        assert_eq!(closes(&buffer), [0.0, 1.0, 2.5]);
+    }
+
+    #[test]
+    fn ticks_move_the_last_bar() {
+        let mut buffer = SeriesBuffer::from(vec![point(0, 10.0)]);
+        assert!(buffer.apply_tick(20.0, time(0) + chrono::Duration::seconds(30)));
+        let last = buffer.candles()[0];
+        assert_eq!((last.close, last.high, last.low), (20.0, 20.0, 8.0));
+        assert!(!buffer.apply_tick(5.0, time(-1)));
+        assert!(!SeriesBuffer::default().apply_tick(5.0, time(0)));
+    }
+
+    #[test]
+    fn resamples_into_bars() {
+        let buffer = SeriesBuffer::from((0..12).map(|minute| point(minute, minute as f64)).collect::<Vec<_>>());
+        let bars = resample(buffer.candles(), Timeframe::M5);
+        // 9:30 to 9:34, 9:35 to 9:39, 9:40 and 9:41
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[0].time, time(0));
+        assert_eq!((bars[0].open, bars[0].high, bars[0].low, bars[0].close), (-1.0, 5.0, -2.0, 4.0));
+        assert_eq!(bars[0].volume, 50.0);
+        assert_eq!(bars[2].close, 11.0);
+        assert_eq!(bars[2].x, time(10).timestamp_millis() as f64);
+
+        // already coarser than asked for
+        assert_eq!(resample(&bars, Timeframe::M1), bars);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // a Tuesday
+        assert_eq!(Timeframe::W1.bar_start(time(0)), Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(Timeframe::D1.bar_start(time(0)), Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn timeframe_of_goes_by_the_smallest_gap() {
+        let candles = |minutes: &[i64]| minutes.iter().map(|&minute| Candle::from(&point(minute, 1.0))).collect::<Vec<_>>();
+        assert_eq!(Timeframe::of(&candles(&[0, 1, 2])), Timeframe::M1);
+        assert_eq!(Timeframe::of(&candles(&[0, 15, 30, 90])), Timeframe::M15);
+        assert_eq!(Timeframe::of(&candles(&[0, 60 * 24])), Timeframe::D1);
+        assert_eq!(Timeframe::of(&candles(&[0, 60 * 24 * 30])), Timeframe::W1);
+        assert_eq!(Timeframe::of(&candles(&[0])), Timeframe::default());
+    }
+
+    #[test]
+    fn resampled_is_redone_when_the_data_changes() {
+        let mut buffer = SeriesBuffer::from(vec![point(0, 0.0), point(5, 5.0)]);
+        let mut resampled = Resampled::default();
+        let (bars, timeframe) = resampled.get(&buffer, Timeframe::M1);
+        assert_eq!((bars.len(), timeframe), (2, Timeframe::M5));
+        buffer.merge([point(6, 6.0)]);
+        assert_eq!(resampled.get(&buffer, Timeframe::M1).0.len(), 3);
+        assert_eq!(resampled.finest(), Timeframe::M1);
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+
+use egui::Color32;
+
+use crate::client::{Callback, TradingBackend};
+
+/// Where a stock's server-side simulation is at. Updated from request callbacks, so it is
+/// shared behind an `Arc<Mutex<_>>`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SimulationState {
+    #[default]
+    Idle,
+    Starting,
+    Running,
+    Pausing,
+    Paused,
+    Stopping,
+    Stopped,
+    Failed(String),
+}
+
+impl SimulationState {
+    pub fn label(&self) -> &str {
+        match self {
+            SimulationState::Idle => "Idle",
+            SimulationState::Starting => "Starting…",
+            SimulationState::Running => "Running",
+            SimulationState::Pausing => "Pausing…",
+            SimulationState::Paused => "Paused",
+            SimulationState::Stopping => "Stopping…",
+            SimulationState::Stopped => "Stopped",
+            SimulationState::Failed(_) => "Failed",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            SimulationState::Running => Color32::from_rgb(0, 255, 0),
+            SimulationState::Paused => Color32::from_rgb(255, 165, 0),
+            SimulationState::Failed(_) => Color32::from_rgb(255, 0, 0),
+            _ => Color32::GRAY,
+        }
+    }
+
+    /// A request is in flight, don't send another one.
+    pub fn is_busy(&self) -> bool {
+        matches!(self, SimulationState::Starting | SimulationState::Pausing | SimulationState::Stopping)
+    }
+
+    pub fn can_start(&self) -> bool {
+        matches!(self, SimulationState::Idle | SimulationState::Paused | SimulationState::Stopped | SimulationState::Failed(_))
+    }
+
+    pub fn can_pause(&self) -> bool {
+        matches!(self, SimulationState::Running)
+    }
+
+    pub fn can_stop(&self) -> bool {
+        matches!(self, SimulationState::Running | SimulationState::Paused)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SimulationAction {
+    Start,
+    Pause,
+    Stop,
+}
+
+/// Starts the simulation, or resumes it when paused.
+pub fn start(state: &Arc<Mutex<SimulationState>>, symbol: &str, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    send(state, symbol, backend, ctx, SimulationAction::Start);
+}
+
+pub fn pause(state: &Arc<Mutex<SimulationState>>, symbol: &str, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    send(state, symbol, backend, ctx, SimulationAction::Pause);
+}
+
+pub fn stop(state: &Arc<Mutex<SimulationState>>, symbol: &str, backend: &dyn TradingBackend, ctx: &egui::Context) {
+    send(state, symbol, backend, ctx, SimulationAction::Stop);
+}
+
+fn send(
+    state: &Arc<Mutex<SimulationState>>,
+    symbol: &str,
+    backend: &dyn TradingBackend,
+    ctx: &egui::Context,
+    action: SimulationAction,
+) {
+    {
+        let mut guard = state.lock().unwrap();
+        let allowed = match action {
+            SimulationAction::Start => guard.can_start(),
+            SimulationAction::Pause => guard.can_pause(),
+            SimulationAction::Stop => guard.can_stop(),
+        };
+        if !allowed {
+            return;
+        }
+        *guard = match action {
+            SimulationAction::Start => SimulationState::Starting,
+            SimulationAction::Pause => SimulationState::Pausing,
+            SimulationAction::Stop => SimulationState::Stopping,
+        };
+    }
+
+    let state = Arc::clone(state);
+    let stock_name = symbol.to_owned();
+    let ctx = ctx.clone();
+    let on_done: Callback<()> = Box::new(move |result| {
+        let next = match (result, action) {
+            (Ok(()), SimulationAction::Start) => SimulationState::Running,
+            (Ok(()), SimulationAction::Pause) => SimulationState::Paused,
+            (Ok(()), SimulationAction::Stop) => SimulationState::Stopped,
+            (Err(e), _) => {
+                log::error!("Simulation request for {stock_name} failed: {e}");
+                SimulationState::Failed(e.to_string())
+            }
+        };
+        log::info!("Simulation for {stock_name} is now {}", next.label());
+        *state.lock().unwrap() = next;
+        ctx.request_repaint();
+    });
+
+    match action {
+        SimulationAction::Start => backend.start_simulation(symbol, on_done),
+        SimulationAction::Pause => backend.pause_simulation(symbol, on_done),
+        SimulationAction::Stop => backend.stop_simulation(symbol, on_done),
+    }
+}
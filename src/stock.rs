@@ -1,95 +1,382 @@
 
-use egui::{Color32, Frame, Margin, RichText, Rounding, Stroke, Theme, Vec2};
-use egui_plot::{Bar, BarChart, BoxElem, BoxPlot, BoxSpread, GridMark, Line, Plot, PlotPoints, PlotUi};
-use std::{ops::RangeInclusive, sync::{Arc, Mutex}};
+use egui::{Align2, Color32, FontId, Frame, Margin, RichText, Rounding, Stroke, Theme, Vec2};
+use egui_plot::{Bar, BarChart, BoxElem, BoxPlot, BoxSpread, GridMark, HLine, Legend, Line, LineStyle, MarkerShape, Plot, PlotBounds, PlotGeometry, PlotItem, PlotItemBase, PlotPoint, PlotPoints, PlotResponse, PlotTransform, PlotUi, Points};
+use std::{collections::HashMap, ops::RangeInclusive, sync::{Arc, Mutex}};
 use chrono::{DateTime, TimeZone, Utc};
-use rusty_trading_model::structs::{Point, TimeRange, TimeSeries, Transaction};
+use rusty_trading_model::structs::{Point, TimeSeries};
 
+use crate::{charts::{self, ChartType}, layout::ChartLayout, scale::{ScaleMode, YScale}, client::{ClientError, TradingBackend}, drawings::Drawings, hotkeys::Quote, indicators::{Indicator, IndicatorSeries}, orders::{BracketLeg, LevelKind, OrderLevel, OrderType, Side}, portfolio::Mark, price::Price, series::{Candle, Resampled, SeriesBuffer, Timeframe}, simulation::{self, SimulationState}, ticket::{OrderTicket, TradingContext}};
+
+/// A failed market data fetch.
+#[derive(Clone)]
+pub struct FetchError {
+    pub symbol: String,
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+impl FetchError {
+    pub fn new(symbol: &str, error: &ClientError) -> Self {
+        Self {
+            symbol: symbol.to_owned(),
+            at: Utc::now(),
+            message: error.to_string(),
+        }
+    }
+}
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Stock {
-    candle_toggle: bool,
-    line_toggle: bool,
+    #[serde(default)]
+    chart_type: ChartType,
+    // Renko and point & figure box, 0 sizes it from the ATR
+    #[serde(default)]
+    box_size: f64,
+    #[serde(default)]
+    scale_mode: ScaleMode,
+    // other watched symbols drawn over this one, scaled to it at the first bar in view
+    #[serde(default)]
+    comparisons: Vec<String>,
+    // the price axis changed units, so the old view means nothing
+    #[serde(skip)]
+    reset_view: bool,
     volume_toggle: bool,
+    #[serde(default)]
+    candle_style: CandleStyle,
+    // height of the volume and indicator panes, as a fraction of the price plot's
+    #[serde(default = "default_pane_ratio")]
+    pane_ratio: f32,
     // managing the stock data, similar to value above
-    time_series: Arc<Mutex<TimeSeries>>,
+    #[serde(default)]
+    series: SeriesBuffer,
+    // bar size on the chart, the series is resampled to it
+    #[serde(default)]
+    timeframe: Timeframe,
+    #[serde(skip)]
+    bars: Resampled,
+    // the compared symbols at our timeframe, kept here so each window resamples only its own
+    #[serde(skip)]
+    comparison_bars: HashMap<String, Resampled>,
+    // drawn by hand, kept with the symbol
+    #[serde(default)]
+    drawings: Drawings,
+    // in the order they were added, which also picks their colors
+    #[serde(default)]
+    indicators: Vec<Indicator>,
+    #[serde(skip)]
+    indicator_series: Vec<IndicatorSeries>,
     // last time the data is updated
     last_update: DateTime<Utc>,
     stock_name: String,
-    qty: String,
-    price: String,
+    // qty and price are persisted at the top level, as before the ticket existed
+    #[serde(flatten)]
+    ticket: OrderTicket,
     open: bool,
     // New fields for enhanced trading
     #[serde(skip)]
     current_price: f32,
+    // the time series has no book, so these only exist in demo mode
     #[serde(skip)]
-    bid_price: f32,
+    bid_price: Option<f32>,
     #[serde(skip)]
-    ask_price: f32,
+    ask_price: Option<f32>,
     #[serde(skip)]
     daily_change: f32,
     #[serde(skip)]
     daily_change_percent: f32,
     #[serde(skip)]
     volume: u64,
+    // timestamp of the bar the quote was derived from, `None` until we have data
+    #[serde(skip)]
+    quote_time: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    quote_is_demo: bool,
+    // cleared by the next successful fetch
+    #[serde(skip)]
+    fetch_error: Option<FetchError>,
+    #[serde(skip)]
+    retry_requested: bool,
+    // order line being dragged on the chart, and whether the pointer was over one (or a drawing's handle) last frame
     #[serde(skip)]
-    show_order_confirmation: bool,
+    line_drag: Option<OrderLevel>,
     #[serde(skip)]
-    pending_order_type: String,
+    line_hovered: bool,
+    // price under the pointer when the chart's menu was opened
+    #[serde(skip)]
+    menu_price: Option<Price>,
+    // not persisted, a restored stock starts its simulation again
+    #[serde(skip)]
+    simulation: Arc<Mutex<SimulationState>>,
+}
+
+/// How the candles are colored and filled.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CandleStyle {
+    up: Color32,
+    down: Color32,
+    // up candles drawn as outlines only
+    hollow: bool,
+}
+
+impl Default for CandleStyle {
+    fn default() -> Self {
+        Self { up: Color32::LIGHT_GREEN, down: Color32::LIGHT_RED, hollow: false }
+    }
+}
+
+/// The time under the pointer on any chart, so every stock window's crosshair and data window
+/// follow it. Windows drawn before the hovered one see it a frame late.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crosshair {
+    last_frame: Option<f64>,
+    this_frame: Option<f64>,
+}
+
+impl Crosshair {
+    /// Call before drawing the stock windows.
+    pub fn start_frame(&mut self) {
+        self.last_frame = self.this_frame.take();
+    }
+
+    fn time(&self) -> Option<f64> {
+        self.this_frame.or(self.last_frame)
+    }
 }
 
 impl Stock {
     pub fn default(stock_name: &str) -> Self {
-        let time_series = TimeSeries::new(TimeRange::Day, Utc::now(), Utc::now(), vec![]);
-        let time_series_arc = Arc::new(Mutex::new(time_series));
         Self {
-            candle_toggle: true,
-            line_toggle: false,
+            chart_type: ChartType::default(),
+            box_size: 0.0,
+            scale_mode: ScaleMode::default(),
+            comparisons: Vec::new(),
+            reset_view: false,
             volume_toggle: true,
-            time_series: time_series_arc,
+            candle_style: CandleStyle::default(),
+            pane_ratio: default_pane_ratio(),
+            series: SeriesBuffer::default(),
+            timeframe: Timeframe::default(),
+            bars: Resampled::default(),
+            comparison_bars: HashMap::new(),
+            drawings: Drawings::default(),
+            indicators: Vec::new(),
+            indicator_series: Vec::new(),
             last_update: Utc::now(),
             stock_name: stock_name.to_owned(),
-            qty: String::new(),
-            price: String::new(),
+            ticket: OrderTicket::default(),
             open: true,
             current_price: 0.0,
-            bid_price: 0.0,
-            ask_price: 0.0,
+            bid_price: None,
+            ask_price: None,
             daily_change: 0.0,
             daily_change_percent: 0.0,
             volume: 0,
-            show_order_confirmation: false,
-            pending_order_type: String::new(),
+            quote_time: None,
+            quote_is_demo: false,
+            fetch_error: None,
+            retry_requested: false,
+            line_drag: None,
+            line_hovered: false,
+            menu_price: None,
+            simulation: Arc::new(Mutex::new(SimulationState::default())),
         }
     }
 
-    pub fn set_time_series(self: &Self, time_series: TimeSeries) {
-        *self.time_series.lock().unwrap() = time_series;
+    /// Merges a (possibly partial) time series into what we already have.
+    pub fn merge_time_series(&mut self, mut time_series: TimeSeries) {
+        if self.series.merge(time_series.data().iter().cloned()) {
+            self.refresh_quote();
+        }
+        self.fetch_error = None;
     }
-}
 
-fn call_start_simulation(stock: &Stock) {
-    let stock_name = stock.stock_name.clone();
-    let url = format!("http://127.0.0.1:3000/simulation_start?stock={}", stock_name);
-    
-    let req = ehttp::Request::json(url, "").unwrap();
-    ehttp::fetch(req, move |response| {
-        match response {
-            Ok(resp) => log::info!("Simulation for {} done...", stock_name),
-            Err(e) => log::error!("Simulation failed due to: {:?}", e),
+    /// Merges points pushed by the streaming feed.
+    pub fn merge_points(&mut self, points: impl IntoIterator<Item = Point>) {
+        if self.series.merge(points) {
+            self.refresh_quote();
         }
-    });
+    }
 
+    pub fn apply_tick(&mut self, price: f64, timestamp: DateTime<Utc>) {
+        if self.series.apply_tick(price, timestamp) {
+            self.refresh_quote();
+        }
+    }
+
+    /// A hotkey order at the bid or ask. There's only a quote in demo mode, otherwise the
+    /// last price stands in for both.
+    pub fn request_quote_order(&mut self, side: Side, quote: Quote, default_qty: u32, trading: &TradingContext<'_>) {
+        let quoted = match quote {
+            Quote::Bid => self.bid_price,
+            Quote::Ask => self.ask_price,
+        };
+        let price = quoted.map(f64::from).or(self.last_price());
+        self.ticket.request_at(side, &self.stock_name, price, default_qty, trading);
+    }
+
+    /// Whatever price the header shows, demo or not.
+    fn last_price(&self) -> Option<f64> {
+        (self.quote_time.is_some() || self.quote_is_demo).then_some(self.current_price as f64)
+    }
+
+    /// Whether the window is showing, closed windows don't get streamed data.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn has_data(&self) -> bool {
+        !self.series.is_empty()
+    }
+
+    /// Where the next incremental fetch should start, `None` means fetch everything.
+    pub fn last_point_time(&self) -> Option<DateTime<Utc>> {
+        self.series.last_time()
+    }
+
+    /// Derives the header numbers from the time series: last close, change against the
+    /// previous session's close (or the session open if that's all we have) and the volume
+    /// traded in the current session. Sessions are UTC calendar days.
+    fn refresh_quote(&mut self) {
+        let points = self.series.candles();
+        self.quote_is_demo = false;
+        self.bid_price = None;
+        self.ask_price = None;
+
+        let Some(last) = points.last() else {
+            self.current_price = 0.0;
+            self.daily_change = 0.0;
+            self.daily_change_percent = 0.0;
+            self.volume = 0;
+            self.quote_time = None;
+            return;
+        };
+
+        let (session_start, reference) = session_reference(points);
+        self.current_price = last.close as f32;
+        self.daily_change = (last.close - reference) as f32;
+        self.daily_change_percent = if reference != 0.0 {
+            ((last.close - reference) / reference * 100.0) as f32
+        } else {
+            0.0
+        };
+        self.volume = points[session_start..].iter().map(|point| point.volume as u64).sum();
+        self.quote_time = Some(last.time);
+    }
+
+    /// Last close and today's reference price from the time series, never the demo quote.
+    pub fn mark(&self) -> Option<Mark> {
+        let points = self.series.candles();
+        let last = points.last()?;
+        let (_, reference) = session_reference(points);
+        Some(Mark { last: last.close, reference })
+    }
+
+    /// Keeps the current time series on screen, only flags the failure.
+    pub fn set_fetch_error(&mut self, error: FetchError) {
+        self.fetch_error = Some(error);
+    }
+
+    /// How the chart is set up, for the layout file.
+    pub fn chart_layout(&self) -> ChartLayout {
+        ChartLayout {
+            open: self.open,
+            chart_type: self.chart_type,
+            box_size: self.box_size,
+            scale_mode: self.scale_mode,
+            comparisons: self.comparisons.clone(),
+            volume_toggle: self.volume_toggle,
+            candle_style: self.candle_style,
+            pane_ratio: self.pane_ratio,
+            timeframe: self.timeframe,
+            indicators: self.indicators.clone(),
+            drawings: self.drawings.clone(),
+        }
+    }
+
+    /// Sets the chart up as a layout file has it, the data and the ticket stay as they are.
+    pub fn apply_layout(&mut self, layout: ChartLayout) {
+        self.open = layout.open;
+        self.chart_type = layout.chart_type;
+        self.box_size = layout.box_size;
+        self.scale_mode = layout.scale_mode;
+        self.comparisons = layout.comparisons;
+        self.volume_toggle = layout.volume_toggle;
+        self.candle_style = layout.candle_style;
+        self.pane_ratio = layout.pane_ratio;
+        self.timeframe = layout.timeframe;
+        self.indicators = layout.indicators;
+        self.drawings = layout.drawings;
+        self.reset_view = true;
+    }
+
+    /// Kicks off the server-side simulation, once when the stock is added or restored.
+    pub fn start_simulation(&self, backend: &dyn TradingBackend, ctx: &egui::Context) {
+        simulation::start(&self.simulation, &self.stock_name, backend, ctx);
+    }
+
+    /// Whether the user asked to refetch since the last call.
+    pub fn take_retry_request(&mut self) -> bool {
+        std::mem::take(&mut self.retry_requested)
+    }
 }
 
-pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
-    // Update mock data for demonstration
-    update_mock_market_data(stock);
+/// Index of the first point in the last session, and the price the session's change is
+/// measured against. `points` must not be empty.
+fn session_reference(points: &[Candle]) -> (usize, f64) {
+    let session = points[points.len() - 1].time.date_naive();
+    let session_start = points
+        .iter()
+        .rposition(|point| point.time.date_naive() != session)
+        .map_or(0, |index| index + 1);
+    let reference = match session_start {
+        0 => points[0].open,
+        _ => points[session_start - 1].close,
+    };
+    (session_start, reference)
+}
+
+/// Returns whether the window is in front, which is where the trading hotkeys go.
+pub fn create_new_stock_window(
+    stock: &mut Stock,
+    ctx: &egui::Context,
+    trading: &TradingContext<'_>,
+    demo_mode: bool,
+    crosshair: &mut Crosshair,
+    watched: &HashMap<String, Arc<Mutex<Stock>>>,
+) -> bool {
+    // Fabricated numbers only when asked for, otherwise go back to what the backend gave us
+    if demo_mode {
+        update_mock_market_data(stock);
+    } else if stock.quote_is_demo {
+        stock.refresh_quote();
+    }
 
-    call_start_simulation(&stock);
-    
     let stock_name = stock.stock_name.clone();
     let mut open = stock.open;
+    let mut in_front = false;
+
+    // our own lock is held already, so never ours
+    let mut others: Vec<&String> = watched.keys().filter(|symbol| **symbol != stock_name).collect();
+    others.sort();
+    // resampled into our own cache, theirs is at their window's timeframe
+    stock.comparison_bars.retain(|symbol, _| stock.comparisons.contains(symbol));
+    let comparisons: Vec<Comparison> = stock
+        .comparisons
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| **symbol != stock_name)
+        .filter_map(|(index, symbol)| {
+            let other = watched.get(symbol)?.lock().unwrap();
+            let (candles, _) = stock.comparison_bars.entry(symbol.clone()).or_default().get(&other.series, stock.timeframe);
+            Some(Comparison {
+                symbol: symbol.clone(),
+                // by where it is in the picker, which still lists the ones not watched
+                color: comparison_color(index),
+                closes: candles.iter().map(|candle| [candle.x, candle.close]).collect(),
+            })
+        })
+        .collect();
 
     if let Some(response) = egui::Window::new(format!("📈 {}", stock_name))
         .open(&mut open)
@@ -98,7 +385,15 @@ pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
             // Header with stock info
             ui.horizontal(|ui| {
                 ui.label(RichText::new(&stock_name).size(20.0).strong().color(Color32::WHITE));
+                if stock.quote_is_demo {
+                    ui.label(RichText::new(" DEMO ").color(Color32::BLACK).background_color(Color32::from_rgb(255, 165, 0)))
+                        .on_hover_text("Market data is simulated, turn off demo mode in the View menu");
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if stock.quote_time.is_none() && !stock.quote_is_demo {
+                        ui.label(RichText::new("No data yet").color(Color32::GRAY));
+                        return;
+                    }
                     let change_color = if stock.daily_change >= 0.0 {
                         Color32::from_rgb(0, 255, 0)
                     } else {
@@ -106,10 +401,25 @@ pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
                     };
                     ui.label(RichText::new(format!("{:.2}%", stock.daily_change_percent)).color(change_color));
                     ui.label(RichText::new(format!("${:.2}", stock.daily_change)).color(change_color));
-                    ui.label(RichText::new(format!("${:.2}", stock.current_price)).size(16.0).strong());
+                    let price_label = ui.label(RichText::new(format!("${:.2}", stock.current_price)).size(16.0).strong());
+                    if let Some(quote_time) = stock.quote_time {
+                        price_label.on_hover_text(format!("Last close as of {}", quote_time.format("%Y-%m-%d %H:%M:%S UTC")));
+                    }
                 });
             });
             
+            // Fetch error badge, the chart below still shows the last good data
+            if let Some(error) = &stock.fetch_error {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(" ⚠ FETCH FAILED ").color(Color32::WHITE).background_color(Color32::from_rgb(150, 0, 0)))
+                        .on_hover_text(format!("at {}", error.at.format("%H:%M:%S UTC")));
+                    ui.label(RichText::new(&error.message).color(Color32::from_rgb(255, 100, 100)));
+                    if ui.small_button("🔄 Retry").clicked() {
+                        stock.retry_requested = true;
+                    }
+                });
+            }
+
             ui.separator();
             
             // Market data row
@@ -117,21 +427,72 @@ pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
                 ui.group(|ui| {
                     ui.label(RichText::new("📊 Market Data").strong());
                     ui.horizontal(|ui| {
-                        ui.label(format!("Bid: ${:.2}", stock.bid_price));
+                        ui.label(format!("Bid: {}", format_optional_price(stock.bid_price)));
                         ui.separator();
-                        ui.label(format!("Ask: ${:.2}", stock.ask_price));
+                        ui.label(format!("Ask: {}", format_optional_price(stock.ask_price)));
                         ui.separator();
                         ui.label(format!("Vol: {}", format_volume(stock.volume)));
                     });
                 });
 
+                ui.group(|ui| {
+                    show_simulation_controls(ui, stock, trading.backend);
+                });
+
                 ui.group(|ui| {
                     ui.label(RichText::new("📊 Chart Options").strong());
                     ui.horizontal(|ui| {
-                        ui.checkbox(&mut stock.candle_toggle, "🕯 Candles");
-                        ui.checkbox(&mut stock.line_toggle, "📈 Line");
+                        egui::ComboBox::from_id_salt(("chart_type", &stock.stock_name))
+                            .selected_text(stock.chart_type.label())
+                            .show_ui(ui, |ui| {
+                                for chart_type in ChartType::ALL {
+                                    ui.selectable_value(&mut stock.chart_type, chart_type, chart_type.label());
+                                }
+                            });
                         ui.checkbox(&mut stock.volume_toggle, "📊 Volume");
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("Up");
+                        ui.color_edit_button_srgba(&mut stock.candle_style.up);
+                        ui.label("Down");
+                        ui.color_edit_button_srgba(&mut stock.candle_style.down);
+                        if matches!(stock.chart_type, ChartType::Candles | ChartType::HeikinAshi | ChartType::Renko) {
+                            ui.checkbox(&mut stock.candle_style.hollow, "Hollow up");
+                        }
+                        if stock.chart_type.uses_boxes() {
+                            ui.label("Box");
+                            ui.add(egui::DragValue::new(&mut stock.box_size).range(0.0..=f64::MAX).speed(0.01).custom_formatter(|size, _| {
+                                if size > 0.0 { format!("{size:.2}") } else { "auto".to_owned() }
+                            }))
+                            .on_hover_text("0 sizes the boxes from the 14 bar ATR, they're never smaller than the tick");
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Scale");
+                        for mode in ScaleMode::ALL {
+                            if ui.selectable_value(&mut stock.scale_mode, mode, mode.label()).changed() {
+                                stock.reset_view = true;
+                            }
+                        }
+                        ui.separator();
+                        show_comparison_picker(ui, &mut stock.comparisons, &others);
+                    });
+                    ui.horizontal(|ui| {
+                        let finest = stock.bars.finest();
+                        for timeframe in Timeframe::ALL {
+                            ui.add_enabled_ui(timeframe >= finest, |ui| {
+                                ui.selectable_value(&mut stock.timeframe, timeframe, timeframe.label())
+                                    .on_disabled_hover_text(format!("The data comes in {} bars", finest.label()));
+                            });
+                        }
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut stock.pane_ratio, 0.1..=1.0)
+                            .text("Pane height")
+                            .custom_formatter(|ratio, _| format!("{:.0}%", ratio * 100.0)),
+                    )
+                    .on_hover_text("Volume and indicator panes, relative to the price chart");
+                    ui.collapsing("📐 Indicators", |ui| show_indicator_picker(ui, &mut stock.indicators));
                 });
             });
             
@@ -141,36 +502,15 @@ pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
             ui.horizontal(|ui| {
                 ui.group(|ui| {
                     ui.label(RichText::new("💰 Trade").strong());
+                    let last_price = stock.last_price();
+                    stock.ticket.show_form(ui, "trade_form", last_price, trading.instruments.get(&stock_name));
+                    stock.ticket.show_bracket_fields(ui);
+
                     ui.horizontal(|ui| {
-                        ui.label("Qty:");
-                        ui.add(egui::TextEdit::singleline(&mut stock.qty).desired_width(60.0));
-                        ui.label("Price:");
-                        ui.add(egui::TextEdit::singleline(&mut stock.price).desired_width(80.0));
-                    });
-                    
-                    ui.horizontal(|ui| {
-                        let buy_button = ui.add(egui::Button::new(RichText::new("BUY").color(Color32::WHITE))
-                            .fill(Color32::from_rgb(0, 150, 0)));
-                        if buy_button.clicked() {
-                            if validate_trade_inputs(&stock.qty, &stock.price) {
-                                stock.pending_order_type = "BUY".to_string();
-                                stock.show_order_confirmation = true;
-                            }
-                        }
-                        
-                        let sell_button = ui.add(egui::Button::new(RichText::new("SELL").color(Color32::WHITE))
-                            .fill(Color32::from_rgb(150, 0, 0)));
-                        if sell_button.clicked() {
-                            if validate_trade_inputs(&stock.qty, &stock.price) {
-                                stock.pending_order_type = "SELL".to_string();
-                                stock.show_order_confirmation = true;
-                            }
-                        }
-                        
-                        if ui.button("📋 Market").on_hover_text("Buy/Sell at market price").clicked() {
-                            stock.price = stock.current_price.to_string();
-                        }
+                        stock.ticket.show_side_buttons(ui, &stock_name, trading);
                     });
+
+                    stock.ticket.show_feedback(ui, trading);
                 });
                 
                 
@@ -178,16 +518,60 @@ pub fn create_new_stock_window(stock: &mut Stock, ctx: &egui::Context) {
             
             ui.separator();
             
-            // Enhanced plot
-            plot_stock_enhanced(ui, stock);
+            // Enhanced plot, with working orders and planned bracket exits
+            let (order_levels, bracket_levels): (Vec<OrderLevel>, Vec<(BracketLeg, Price)>) = {
+                let book = trading.orders.lock().unwrap();
+                let bracket_levels = book.active_brackets(&stock_name).flat_map(|bracket| bracket.levels()).collect();
+                (book.levels(&stock_name), bracket_levels)
+            };
+            let tick_size = trading.instruments.get(&stock_name).tick_size;
+            match plot_stock_enhanced(ui, stock, &order_levels, &bracket_levels, tick_size, crosshair, &comparisons) {
+                Some(ChartAction::Order { side, order_type, price }) => {
+                    stock.ticket.request_priced(side, &stock_name, order_type, price, trading);
+                }
+                Some(ChartAction::Move { client_id, kind, price }) => {
+                    stock.ticket.request_move(ctx, &client_id, kind, price, trading);
+                }
+                None => {}
+            }
         }) {
         // Update the open state
         stock.open = open;
+        in_front = ctx.top_layer_id() == Some(response.response.layer_id);
     }
     
     // Order confirmation dialog (outside the main window to avoid borrowing issues)
-    if stock.show_order_confirmation {
-        show_order_confirmation_dialog(stock, ctx);
+    let confirm_id = egui::Id::new(("confirm_order", &stock_name));
+    stock.ticket.show_confirmation(ctx, confirm_id, trading);
+    in_front
+}
+
+fn show_simulation_controls(ui: &mut egui::Ui, stock: &Stock, backend: &dyn TradingBackend) {
+    let state = stock.simulation.lock().unwrap().clone();
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("🎬 Simulation").strong());
+        let status = ui.label(RichText::new(format!("● {}", state.label())).color(state.color()));
+        if let SimulationState::Failed(message) = &state {
+            status.on_hover_text(message);
+        }
+        if state.is_busy() {
+            ui.spinner();
+        }
+    });
+    ui.horizontal(|ui| {
+        let start_text = if state == SimulationState::Paused { "▶ Resume" } else { "▶ Start" };
+        if ui.add_enabled(state.can_start(), egui::Button::new(start_text)).clicked() {
+            simulation::start(&stock.simulation, &stock.stock_name, backend, ui.ctx());
+        }
+        if ui.add_enabled(state.can_pause(), egui::Button::new("⏸ Pause")).clicked() {
+            simulation::pause(&stock.simulation, &stock.stock_name, backend, ui.ctx());
+        }
+        if ui.add_enabled(state.can_stop(), egui::Button::new("⏹ Stop")).clicked() {
+            simulation::stop(&stock.simulation, &stock.stock_name, backend, ui.ctx());
+        }
+    });
+    if let SimulationState::Failed(message) = &state {
+        ui.label(RichText::new(message).color(Color32::from_rgb(255, 100, 100)).small());
     }
 }
 
@@ -197,11 +581,19 @@ fn update_mock_market_data(stock: &mut Stock) {
     let time_factor = (Utc::now().timestamp() as f32 / 10.0) % (2.0 * PI);
     
     stock.current_price = 175.5 + (time_factor.sin() * 5.0);
-    stock.bid_price = stock.current_price - 0.05;
-    stock.ask_price = stock.current_price + 0.05;
+    stock.bid_price = Some(stock.current_price - 0.05);
+    stock.ask_price = Some(stock.current_price + 0.05);
     stock.daily_change = time_factor.sin() * 2.5;
     stock.daily_change_percent = (stock.daily_change / stock.current_price) * 100.0;
     stock.volume = 1_250_000 + ((time_factor * 1000.0) as u64);
+    stock.quote_is_demo = true;
+}
+
+fn format_optional_price(price: Option<f32>) -> String {
+    match price {
+        Some(price) => format!("${:.2}", price),
+        None => "—".to_owned(),
+    }
 }
 
 fn format_volume(volume: u64) -> String {
@@ -214,118 +606,538 @@ fn format_volume(volume: u64) -> String {
     }
 }
 
-fn validate_trade_inputs(qty: &str, price: &str) -> bool {
-    qty.parse::<u32>().is_ok() && price.parse::<f32>().is_ok()
+/// What was asked for on the chart, carried out by the window once the plot is drawn.
+enum ChartAction {
+    /// From the right-click menu; `price` is the limit, or the stop for a stop order.
+    Order { side: Side, order_type: OrderType, price: Price },
+    /// An order line dragged to a new price.
+    Move { client_id: String, kind: LevelKind, price: Price },
 }
 
-fn show_order_confirmation_dialog(stock: &mut Stock, ctx: &egui::Context) {
-    egui::Window::new("🔔 Confirm Order")
-        .collapsible(false)
-        .resizable(false)
-        .show(ctx, |ui| {
-            ui.label(RichText::new("Order Confirmation").size(16.0).strong());
-            ui.separator();
-            
-            ui.label(format!("Symbol: {}", stock.stock_name));
-            ui.label(format!("Type: {}", stock.pending_order_type));
-            ui.label(format!("Quantity: {}", stock.qty));
-            ui.label(format!("Price: ${}", stock.price));
-            
-            let total = stock.qty.parse::<u32>().unwrap_or(0) as f32 * stock.price.parse::<f32>().unwrap_or(0.0);
-            ui.label(format!("Total: ${:.2}", total));
-            
-            ui.separator();
-            
-            ui.horizontal(|ui| {
-                let confirm_button = ui.add(egui::Button::new(RichText::new("✅ Confirm").color(Color32::WHITE))
-                    .fill(Color32::from_rgb(0, 150, 0)));
-                if confirm_button.clicked() {
-                    execute_trade(stock);
-                    stock.show_order_confirmation = false;
-                }
-                
-                let cancel_button = ui.add(egui::Button::new(RichText::new("❌ Cancel").color(Color32::WHITE))
-                    .fill(Color32::from_rgb(150, 0, 0)));
-                if cancel_button.clicked() {
-                    stock.show_order_confirmation = false;
-                }
-            });
-        });
-}
+fn plot_stock_enhanced(
+    ui: &mut egui::Ui,
+    stock: &mut Stock,
+    order_levels: &[OrderLevel],
+    bracket_levels: &[(BracketLeg, Price)],
+    tick_size: Price,
+    crosshair: &mut Crosshair,
+    comparisons: &[Comparison],
+) -> Option<ChartAction> {
+    // how close the pointer has to be to an order line to grab it
+    const GRAB_DISTANCE: f32 = 6.0;
 
-fn execute_trade(stock: &mut Stock) {
-    let url = "http://127.0.0.1:3000/transaction";
-    let stock_name = stock.stock_name.clone();
-    let price = stock.price.parse::<f64>().unwrap();
-    let qty = stock.qty.parse::<u32>().unwrap();
-    
-    let transaction = if stock.pending_order_type == "BUY" {
-        Transaction::buy(stock_name, price, qty)
-    } else {
-        Transaction::sell(stock_name, price, qty)
-    };
-    
-    let val = serde_json::to_value(transaction).unwrap();
-    log::info!("Executing trade: {val}");
-    let req = ehttp::Request::json(url, &val).unwrap();
-    ehttp::fetch(req, move |response| {
-        match response {
-            Ok(resp) => log::info!("Trade executed successfully: {:?}", resp.text().unwrap()),
-            Err(e) => log::error!("Trade execution failed: {:?}", e),
+    let last_price = stock.last_price().map(Price::from_f64);
+    let (points, timeframe) = stock.bars.get(&stock.series, stock.timeframe);
+    // gaps (nights, weekends) would make the average too wide
+    let time_step = estimate_time_step(points).min(timeframe.millis() as f64);
+    // panes below share the time axis with the prices
+    let link = egui::Id::new(("chart_link", &stock.stock_name));
+
+    // keep the computed series in step with the picker, reusing the ones that still match
+    stock.indicator_series.truncate(stock.indicators.len());
+    for (index, indicator) in stock.indicators.iter().enumerate() {
+        match stock.indicator_series.get(index) {
+            Some(series) if series.indicator() == *indicator => {}
+            Some(_) => stock.indicator_series[index] = IndicatorSeries::new(*indicator),
+            None => stock.indicator_series.push(IndicatorSeries::new(*indicator)),
         }
-    });
-    
-    // Clear form after successful submission
-    stock.qty.clear();
-    stock.price.clear();
-}
+    }
+    for series in &mut stock.indicator_series {
+        series.update(points);
+    }
+
+    let bar = crosshair.time().and_then(|time| bar_at(points, time, time_step)).or(points.len().checked_sub(1));
+    show_data_window(ui, points, &stock.indicator_series, bar, timeframe);
+    stock.drawings.show_toolbar(ui);
 
-fn plot_stock_enhanced(ui: &mut egui::Ui, stock: &mut Stock) -> egui::Response {
-    let points = collect_time_series_points(&stock.time_series);
-    let time_step = estimate_time_step(&points);
+    let box_size = chart_box_size(stock.chart_type, stock.box_size, points, tick_size);
+    let bars = stock.chart_type.bars(points, box_size, time_step * 0.8);
+    let boxes = match stock.chart_type {
+        ChartType::PointAndFigure => bars.iter().map(|column| charts::column_boxes(column, box_size)).sum(),
+        _ => bars.len(),
+    };
+    if stock.chart_type.uses_boxes() && boxes >= charts::MAX_BOXES {
+        ui.label(RichText::new(format!("⚠ Box ${box_size:.2} is too small, only the first {} boxes are drawn", charts::MAX_BOXES))
+            .small()
+            .color(Color32::from_rgb(255, 165, 0)));
+    }
 
-    let plot = Plot::new("enhanced_stock_plot")
+    let mut plot = Plot::new("enhanced_stock_plot")
         .view_aspect(2.0)
         .min_size(Vec2::new(200.0, 100.0))
         .set_margin_fraction(Vec2::new(0.05, 0.1))
         .show_axes(true)
         .allow_zoom(true)
-        .allow_drag(true)
+        // dragging an order line mustn't pan the chart as well
+        .allow_drag(stock.line_drag.is_none() && !stock.line_hovered && !stock.drawings.is_busy())
         .allow_scroll(true)
         .show_background(false)
         .show_grid(false)
-        .x_axis_formatter(format_time_axis)
-        .show_x(true)
-        .show_y(true);
+        .x_axis_formatter(move |mark, range| format_time_axis(mark, range, timeframe))
+        // the crosshair replaces the coordinates next to the pointer
+        .show_x(false)
+        .show_y(false)
+        .y_axis_min_width(PANE_AXIS_WIDTH)
+        .legend(Legend::default())
+        .link_axis(link, [true, false]);
+    if stock.scale_mode != ScaleMode::Price {
+        let mode = stock.scale_mode;
+        plot = plot.y_axis_formatter(move |mark, _| mode.format(mark.value));
+    }
+    if std::mem::take(&mut stock.reset_view) {
+        plot = plot.reset();
+    }
+
+    let mut action = None;
+    let response = plot.show(ui, |plot_ui| {
+        // everything on the price axis goes through the scale, prices stay prices everywhere else
+        let view_start = plot_ui.plot_bounds().min()[0];
+        let first_visible = points.iter().find(|point| point.x >= view_start).or(points.first());
+        let scale = YScale::new(stock.scale_mode, first_visible.map_or(1.0, |point| point.close));
+
+        plot_prices(plot_ui, &stock.stock_name, stock.chart_type, &bars, time_step, stock.candle_style, box_size, scale);
+        for comparison in comparisons {
+            plot_comparison(plot_ui, comparison, first_visible.map_or(view_start, |point| point.x), scale);
+        }
+
+        for (index, series) in stock.indicator_series.iter().enumerate() {
+            if series.indicator().is_overlay() {
+                plot_indicator_lines(plot_ui, points, series, indicator_color(index), scale);
+            }
+        }
+
+        // the order lines are left out of the legend, their colors say enough
+        for &(leg, price) in bracket_levels {
+            plot_ui.hline(
+                HLine::new("", scale.to_plot(price.to_f64()))
+                    .color(leg.color())
+                    .style(LineStyle::dashed_loose()),
+            );
+        }
+
+        // the pointer's price, on the tick
+        let response = plot_ui.response().clone();
+        let pointer = plot_ui.pointer_coordinate();
+        let pointer_price = pointer.map(|pointer| Price::from_f64(scale.to_price(pointer.y)).round_to(tick_size));
+        let hovered = pointer.filter(|_| response.hovered()).and_then(|pointer| {
+            let pointer_y = plot_ui.screen_from_plot(pointer).y;
+            order_levels
+                .iter()
+                .filter(|level| level.movable)
+                .map(|level| (level, (plot_ui.transform().position_from_point_y(scale.to_plot(level.price.to_f64())) - pointer_y).abs()))
+                .filter(|&(_, distance)| distance <= GRAB_DISTANCE)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(level, _)| level)
+        });
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            stock.line_drag = hovered.cloned();
+        }
+        // order lines come first when a drawing's handle is right there too
+        let handle_hovered = stock.drawings.interact(plot_ui, &response, hovered.is_some() || stock.line_drag.is_some(), scale);
+        stock.line_hovered = hovered.is_some() || handle_hovered;
+        if hovered.is_some() || stock.line_drag.is_some() {
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeVertical);
+        }
+
+        for level in order_levels {
+            let grabbed = stock.line_drag.as_ref().or(hovered).is_some_and(|grabbed| grabbed.client_id == level.client_id && grabbed.kind == level.kind);
+            plot_ui.hline(
+                HLine::new("", scale.to_plot(level.price.to_f64()))
+                    .color(level.color())
+                    .width(if grabbed { 2.5 } else { 1.5 }),
+            );
+        }
+        if let (Some(dragged), Some(price)) = (&stock.line_drag, pointer_price) {
+            plot_ui.hline(
+                HLine::new("", scale.to_plot(price.to_f64()))
+                    .color(dragged.color())
+                    .style(LineStyle::dashed_dense()),
+            );
+        }
+
+        if let Some(dragged) = stock.line_drag.take_if(|_| response.drag_stopped()) {
+            // let go outside the plot or where it started, nothing to do
+            action = pointer_price
+                .filter(|&price| price != dragged.price && price.is_positive())
+                .map(|price| ChartAction::Move { client_id: dragged.client_id, kind: dragged.kind, price });
+        }
+        if response.secondary_clicked() {
+            stock.menu_price = pointer_price;
+        }
+        scale
+    });
+    let scale = response.inner;
+
+    response.response.context_menu(|ui| {
+        let Some(price) = stock.menu_price.filter(|price| price.is_positive()) else {
+            ui.close();
+            return;
+        };
+        ui.label(RichText::new(format!("{} at ${price}", stock.stock_name)).strong());
+        ui.separator();
+        // limits below the market buy and above it sell, stops the other way round
+        let below = last_price.is_none_or(|last| price <= last);
+        let above = last_price.is_none_or(|last| price >= last);
+        let choices = [
+            (below, Side::Buy, OrderType::Limit),
+            (below, Side::Sell, OrderType::Stop),
+            (above, Side::Sell, OrderType::Limit),
+            (above, Side::Buy, OrderType::Stop),
+        ];
+        for (offered, side, order_type) in choices {
+            if !offered {
+                continue;
+            }
+            let side_name = match side {
+                Side::Buy => "Buy",
+                Side::Sell => "Sell",
+            };
+            let text = format!("{side_name} {} at {price}", order_type.label().to_lowercase());
+            if ui.button(RichText::new(text).color(side.color())).clicked() {
+                action = Some(ChartAction::Order { side, order_type, price });
+                ui.close();
+            }
+        }
+    });
+
+    // the element under the pointer, whatever the chart type draws
+    let pointer_free = stock.line_drag.is_none() && !stock.line_hovered && !stock.drawings.is_busy();
+    if let Some(pointer) = response.response.hover_pos().filter(|_| pointer_free) {
+        let time = response.transform.value_from_position(pointer).x;
+        let tolerance = if stock.chart_type.uses_boxes() { f64::INFINITY } else { time_step };
+        if let Some(index) = bar_at(&bars, time, tolerance) {
+            let previous_close = index.checked_sub(1).map(|previous| bars[previous].close);
+            response.response.clone().on_hover_text_at_pointer(candle_tooltip(&bars[index], previous_close, timeframe));
+        }
+    }
+
+    paint_crosshair(ui, &response, crosshair, Some(timeframe), |value| match scale.mode {
+        ScaleMode::Percent => scale.mode.format(value),
+        _ => Price::from_f64(scale.to_price(value)).round_to(tick_size).to_string(),
+    });
+
+    // the price plot's height follows its width, the panes follow that
+    let panes = Panes { link, height: (response.response.rect.height() * stock.pane_ratio).max(40.0), timeframe };
+    if stock.volume_toggle {
+        let response = panes.plot("volume_pane")
+            .include_y(0.0)
+            .y_axis_formatter(|mark, _| format_volume(mark.value.max(0.0) as u64))
+            .show(ui, |plot_ui| plot_volume(points, plot_ui, time_step));
+        paint_crosshair(ui, &response, crosshair, None, |value| format_volume(value.max(0.0) as u64));
+    }
+    plot_indicator_panes(ui, points, &stock.indicator_series, panes, time_step, crosshair);
+
+    action
+}
+
+fn default_pane_ratio() -> f32 {
+    0.25
+}
+
+// y axis width shared by the price plot and the panes below it, so their time axes line up
+const PANE_AXIS_WIDTH: f32 = 56.0;
+
+/// The plots under the prices, following their time axis.
+#[derive(Clone, Copy)]
+struct Panes {
+    link: egui::Id,
+    height: f32,
+    timeframe: Timeframe,
+}
+
+impl Panes {
+    fn plot(&self, id_source: impl std::hash::Hash) -> Plot<'static> {
+        let timeframe = self.timeframe;
+        Plot::new(id_source)
+            .height(self.height)
+            .show_background(false)
+            .show_grid(false)
+            .show_axes([false, true])
+            .y_axis_min_width(PANE_AXIS_WIDTH)
+            .x_axis_formatter(move |mark, range| format_time_axis(mark, range, timeframe))
+            .legend(Legend::default())
+            .show_x(false)
+            .show_y(false)
+            .link_axis(self.link, [true, false])
+    }
+}
+
+/// The bar closest to `time`, unless that's more than a bar away.
+fn bar_at(points: &[Candle], time: f64, time_step: f64) -> Option<usize> {
+    let after = points.partition_point(|point| point.x < time);
+    [after.checked_sub(1), Some(after)]
+        .into_iter()
+        .flatten()
+        .filter(|&index| index < points.len())
+        .min_by(|&a, &b| (points[a].x - time).abs().total_cmp(&(points[b].x - time).abs()))
+        .filter(|&index| (points[index].x - time).abs() <= time_step)
+}
+
+/// One line with the bar's OHLCV and every indicator's values at it.
+fn show_data_window(ui: &mut egui::Ui, points: &[Candle], indicator_series: &[IndicatorSeries], bar: Option<usize>, timeframe: Timeframe) {
+    let Some(index) = bar else {
+        ui.label(RichText::new("No data").monospace().color(Color32::GRAY));
+        return;
+    };
+    let candle = &points[index];
+    let reference = index.checked_sub(1).map_or(candle.open, |previous| points[previous].close);
+    ui.horizontal_wrapped(|ui| {
+        ui.label(RichText::new(format_bar_time(candle.time, timeframe)).monospace().strong());
+        ui.label(
+            RichText::new(format!("O {:.2}  H {:.2}  L {:.2}  C {:.2}", candle.open, candle.high, candle.low, candle.close))
+                .monospace(),
+        );
+        let change_color = if candle.close >= reference { Color32::from_rgb(0, 200, 0) } else { Color32::from_rgb(200, 0, 0) };
+        ui.label(RichText::new(format_change(candle.close, reference)).monospace().color(change_color));
+        ui.label(RichText::new(format!("Vol {}", format_volume(candle.volume.max(0.0) as u64))).monospace());
+        for (series_index, series) in indicator_series.iter().enumerate() {
+            let Some(values) = series.values().get(index) else {
+                continue;
+            };
+            let text = values
+                .iter()
+                .take(series.indicator().outputs().len())
+                .map(|value| value.map_or("—".to_owned(), |value| format!("{value:.2}")))
+                .collect::<Vec<_>>()
+                .join(" / ");
+            ui.separator();
+            ui.label(RichText::new(series.indicator().label()).monospace().color(indicator_color(series_index)));
+            ui.label(RichText::new(text).monospace());
+        }
+    });
+}
+
+/// The crosshair's time line, and where the pointer is, its value line. Each gets a label on
+/// its axis: the time only under plots that show the time axis.
+fn paint_crosshair<R>(
+    ui: &egui::Ui,
+    plot: &PlotResponse<R>,
+    crosshair: &mut Crosshair,
+    time_axis: Option<Timeframe>,
+    format_value: impl Fn(f64) -> String,
+) {
+    let rect = plot.response.rect;
+    let pointer = plot.response.hover_pos().map(|pos| plot.transform.value_from_position(pos));
+    if let Some(pointer) = pointer {
+        crosshair.this_frame = Some(pointer.x);
+    }
+    let Some(time) = crosshair.time() else {
+        return;
+    };
+
+    let painter = ui.painter();
+    let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+    let label = |pos, anchor: Align2, text: String| {
+        let galley = painter.layout_no_wrap(text, FontId::monospace(11.0), ui.visuals().strong_text_color());
+        let label_rect = anchor.anchor_size(pos, galley.size()).expand(2.0);
+        painter.rect_filled(label_rect, 2.0, ui.visuals().widgets.inactive.bg_fill);
+        painter.galley(label_rect.min + Vec2::splat(2.0), galley, Color32::PLACEHOLDER);
+    };
+
+    let x = plot.transform.position_from_point_x(time);
+    if rect.x_range().contains(x) {
+        painter.vline(x, rect.y_range(), stroke);
+        if let Some(timeframe) = time_axis {
+            let time = Utc.timestamp_millis_opt(time as i64).single().map(|time| format_bar_time(time, timeframe));
+            label(egui::pos2(x, rect.bottom()), Align2::CENTER_TOP, time.unwrap_or_default());
+        }
+    }
+    if let Some(pointer) = pointer {
+        let y = plot.transform.position_from_point_y(pointer.y);
+        painter.hline(rect.x_range(), y, stroke);
+        label(egui::pos2(rect.left(), y), Align2::RIGHT_CENTER, format_value(pointer.y));
+    }
+}
+
+const INDICATOR_COLORS: [Color32; 6] = [
+    Color32::from_rgb(255, 200, 0),
+    Color32::from_rgb(0, 190, 255),
+    Color32::from_rgb(255, 100, 200),
+    Color32::from_rgb(160, 120, 255),
+    Color32::from_rgb(255, 140, 60),
+    Color32::from_rgb(120, 220, 160),
+];
+
+fn indicator_color(index: usize) -> Color32 {
+    INDICATOR_COLORS[index % INDICATOR_COLORS.len()]
+}
+
+/// Another watched symbol's closes, drawn over the chart.
+struct Comparison {
+    symbol: String,
+    color: Color32,
+    closes: Vec<[f64; 2]>,
+}
+
+const COMPARISON_COLORS: [Color32; 4] = [
+    Color32::from_rgb(230, 230, 230),
+    Color32::from_rgb(255, 120, 120),
+    Color32::from_rgb(140, 255, 140),
+    Color32::from_rgb(200, 160, 255),
+];
+
+fn comparison_color(index: usize) -> Color32 {
+    COMPARISON_COLORS[index % COMPARISON_COLORS.len()]
+}
+
+/// The compared symbols in their colors, and a menu of the other watched ones to add.
+fn show_comparison_picker(ui: &mut egui::Ui, comparisons: &mut Vec<String>, others: &[&String]) {
+    let mut removed = None;
+    for (index, symbol) in comparisons.iter().enumerate() {
+        let watched = others.contains(&symbol);
+        let text = RichText::new(symbol).color(if watched { comparison_color(index) } else { Color32::GRAY });
+        ui.label(text).on_hover_text(if watched { "Compared from the first bar in view" } else { "Not watched any more" });
+        if ui.small_button("✖").clicked() {
+            removed = Some(index);
+        }
+    }
+    if let Some(index) = removed {
+        comparisons.remove(index);
+    }
+
+    let addable: Vec<&String> = others.iter().copied().filter(|symbol| !comparisons.contains(symbol)).collect();
+    ui.add_enabled_ui(!addable.is_empty(), |ui| {
+        ui.menu_button("➕ Compare", |ui| {
+            for symbol in addable {
+                if ui.button(symbol).clicked() {
+                    comparisons.push(symbol.clone());
+                    ui.close();
+                }
+            }
+        })
+        .response
+        .on_disabled_hover_text("Watch another symbol to compare with");
+    });
+}
+
+/// Scaled so it starts where our own close does at the first bar in view.
+fn plot_comparison(plot_ui: &mut PlotUi, comparison: &Comparison, start: f64, scale: YScale) {
+    let Some(base) = comparison.closes.iter().find(|close| close[0] >= start).map(|close| close[1]).filter(|&base| base > 0.0) else {
+        return;
+    };
+    let line_points: PlotPoints = comparison.closes.iter().map(|&[x, close]| [x, scale.to_plot(close / base * scale.base)]).collect();
+    plot_ui.line(Line::new(&comparison.symbol, line_points).color(comparison.color).width(1.5));
+}
 
-    plot.show(ui, |plot_ui| {
-        // Plot line chart if enabled
-        if stock.line_toggle {
-            plot_line(&points, plot_ui);
+/// The active indicators with their parameters, and buttons to add more.
+fn show_indicator_picker(ui: &mut egui::Ui, indicators: &mut Vec<Indicator>) {
+    let mut removed = None;
+    for (index, indicator) in indicators.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(indicator.name()).strong().color(indicator_color(index)));
+            show_indicator_params(ui, indicator);
+            if ui.small_button("🗑").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        indicators.remove(index);
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Add:");
+        for indicator in Indicator::ALL {
+            if ui.small_button(indicator.name()).clicked() {
+                indicators.push(indicator);
+            }
         }
-        
-        // Plot candlestick chart if enabled
-        if stock.candle_toggle {
-            plot_candle(&points, plot_ui, time_step);
+    });
+}
+
+fn show_indicator_params(ui: &mut egui::Ui, indicator: &mut Indicator) {
+    fn period(ui: &mut egui::Ui, value: &mut usize) {
+        ui.add(egui::DragValue::new(value).range(1..=500));
+    }
+    match indicator {
+        Indicator::Sma { period: value }
+        | Indicator::Ema { period: value }
+        | Indicator::Rsi { period: value }
+        | Indicator::Atr { period: value } => period(ui, value),
+        Indicator::Vwap => {}
+        Indicator::Bollinger { period: value, width } => {
+            period(ui, value);
+            ui.add(egui::DragValue::new(width).range(0.5..=5.0).speed(0.1).prefix("± ").suffix(" σ"));
         }
-        
-        // Plot volume bars if enabled
-        if stock.volume_toggle {
-            plot_volume(&points, plot_ui, time_step);
+        Indicator::Macd { fast, slow, signal } => {
+            period(ui, fast);
+            period(ui, slow);
+            period(ui, signal);
         }
-    }).response
+    }
 }
 
-fn collect_time_series_points(time_series: &Arc<Mutex<TimeSeries>>) -> Vec<Point> {
-    let mut guard = time_series.lock().unwrap();
-    guard.data().clone()
+/// One line per output, skipping the bars the indicator is still warming up on. Bands are
+/// dashed.
+fn plot_indicator_lines(plot_ui: &mut PlotUi, points: &[Candle], series: &IndicatorSeries, color: Color32, scale: YScale) {
+    let indicator = series.indicator();
+    let outputs = indicator.outputs();
+    for (output, output_name) in outputs.iter().enumerate() {
+        let line_points: PlotPoints = points
+            .iter()
+            .zip(series.values())
+            .filter_map(|(point, values)| values[output].map(|value| [point.x, scale.to_plot(value)]))
+            .collect();
+        let name = if outputs.len() == 1 { indicator.label() } else { format!("{} {output_name}", indicator.label()) };
+        let line = Line::new(name, line_points).color(color);
+        plot_ui.line(if output == 0 { line } else { line.style(LineStyle::dashed_dense()) });
+    }
 }
 
-fn timestamp_to_f64(timestamp: &DateTime<Utc>) -> f64 {
-    timestamp.timestamp_millis() as f64
+/// A pane per oscillator under the price plot, sharing its time axis.
+fn plot_indicator_panes(
+    ui: &mut egui::Ui,
+    points: &[Candle],
+    indicator_series: &[IndicatorSeries],
+    panes: Panes,
+    time_step: f64,
+    crosshair: &mut Crosshair,
+) {
+    for (index, series) in indicator_series.iter().enumerate() {
+        let indicator = series.indicator();
+        if indicator.is_overlay() {
+            continue;
+        }
+        let color = indicator_color(index);
+        let mut plot = panes.plot(("indicator_pane", index));
+        if let Indicator::Rsi { .. } = indicator {
+            plot = plot.include_y(0.0).include_y(100.0);
+        }
+
+        let response = plot.show(ui, |plot_ui| match indicator {
+            Indicator::Rsi { .. } => {
+                for level in [30.0, 70.0] {
+                    plot_ui.hline(HLine::new(format!("{level}"), level).color(Color32::GRAY).style(LineStyle::dashed_loose()));
+                }
+                plot_indicator_lines(plot_ui, points, series, color, YScale::default());
+            }
+            Indicator::Macd { .. } => {
+                let bar_width = (time_step * 0.6).max(1.0);
+                let histogram: Vec<Bar> = points
+                    .iter()
+                    .zip(series.values())
+                    .filter_map(|(point, values)| values[2].map(|value| (point.x, value)))
+                    .map(|(x, value)| {
+                        let color = if value >= 0.0 { Color32::from_rgb(0, 160, 0) } else { Color32::from_rgb(180, 0, 0) };
+                        Bar::new(x, value).width(bar_width).fill(color)
+                    })
+                    .collect();
+                plot_ui.bar_chart(BarChart::new("Histogram", histogram));
+                for (output, line_color) in [(0, color), (1, Color32::from_rgb(255, 140, 0))] {
+                    let line_points: PlotPoints = points
+                        .iter()
+                        .zip(series.values())
+                        .filter_map(|(point, values)| values[output].map(|value| [point.x, value]))
+                        .collect();
+                    plot_ui.line(Line::new(indicator.outputs()[output], line_points).color(line_color));
+                }
+            }
+            _ => plot_indicator_lines(plot_ui, points, series, color, YScale::default()),
+        });
+        paint_crosshair(ui, &response, crosshair, None, |value| format!("{value:.2}"));
+    }
 }
 
-fn estimate_time_step(points: &[Point]) -> f64 {
+fn estimate_time_step(points: &[Candle]) -> f64 {
     const DEFAULT_STEP_MS: f64 = 60_000.0;
 
     if points.len() < 2 {
@@ -336,11 +1148,7 @@ fn estimate_time_step(points: &[Point]) -> f64 {
     let mut count = 0;
 
     for window in points.windows(2) {
-        let diff = window[1]
-            .timestamp
-            .signed_duration_since(window[0].timestamp)
-            .num_milliseconds()
-            .abs() as f64;
+        let diff = (window[1].x - window[0].x).abs();
 
         if diff > 0.0 {
             total += diff;
@@ -355,17 +1163,9 @@ fn estimate_time_step(points: &[Point]) -> f64 {
     }
 }
 
-fn time_bounds(points: &[Point]) -> Option<(f64, f64)> {
-    points.iter().map(|p| timestamp_to_f64(&p.timestamp)).fold(
-        None,
-        |acc, ts| match acc {
-            Some((min_ts, max_ts)) => Some((min_ts.min(ts), max_ts.max(ts))),
-            None => Some((ts, ts)),
-        },
-    )
-}
-
-fn format_time_axis(mark: GridMark, range: &RangeInclusive<f64>) -> String {
+/// Dates for daily and weekly bars, times of day for intraday ones, with the date added once
+/// the view spans more than a day.
+fn format_time_axis(mark: GridMark, range: &RangeInclusive<f64>, timeframe: Timeframe) -> String {
     if !mark.value.is_finite() {
         return String::new();
     }
@@ -374,18 +1174,19 @@ fn format_time_axis(mark: GridMark, range: &RangeInclusive<f64>) -> String {
     let date_time = Utc.timestamp_millis(mark.value as i64);
 
     const DAY_MS: f64 = 86_400_000.0;
-    const HOUR_MS: f64 = 3_600_000.0;
+    const YEAR_MS: f64 = 365.0 * DAY_MS;
 
-    if span_ms > DAY_MS {
-        date_time.format("%Y-%m-%d").to_string()
-    } else if span_ms > HOUR_MS {
-        date_time.format("%H:%M").to_string()
+    let format = if !timeframe.is_intraday() {
+        if span_ms > 2.0 * YEAR_MS { "%Y-%m" } else { "%Y-%m-%d" }
+    } else if span_ms > DAY_MS {
+        "%m-%d %H:%M"
     } else {
-        date_time.format("%H:%M:%S").to_string()
-    }
+        "%H:%M"
+    };
+    date_time.format(format).to_string()
 }
 
-fn plot_volume(points: &[Point], plot_ui: &mut PlotUi, time_step: f64) {
+fn plot_volume(points: &[Candle], plot_ui: &mut PlotUi, time_step: f64) {
     if points.is_empty() {
         return;
     }
@@ -395,7 +1196,7 @@ fn plot_volume(points: &[Point], plot_ui: &mut PlotUi, time_step: f64) {
     let volume_bars: Vec<Bar> = points
         .iter()
         .map(|point| {
-            Bar::new(timestamp_to_f64(&point.timestamp), point.volume as f64)
+            Bar::new(point.x, point.volume)
                 .width(bar_width)
                 .fill(Color32::from_rgba_unmultiplied(100, 100, 100, 100))
         })
@@ -408,66 +1209,204 @@ fn plot_volume(points: &[Point], plot_ui: &mut PlotUi, time_step: f64) {
     plot_ui.bar_chart(volume_chart);
 }
 
-fn plot_line(points: &[Point], plot_ui: &mut PlotUi) {
-    if points.is_empty() {
+/// The Renko and point & figure box: the one set, or else the last 14 bar ATR on the tick. Never
+/// under the tick either way.
+fn chart_box_size(chart_type: ChartType, box_size: f64, points: &[Candle], tick_size: Price) -> f64 {
+    if !chart_type.uses_boxes() {
+        return 0.0;
+    }
+    let tick = tick_size.to_f64().max(0.01);
+    if box_size > 0.0 {
+        return box_size.max(tick);
+    }
+    let atr = IndicatorSeries::new(Indicator::Atr { period: 14 }).update(points).last().and_then(|values| values[0]);
+    atr.map_or(tick, |atr| Price::from_f64(atr).round_to(tick_size).to_f64().max(tick))
+}
+
+/// The prices, named after the symbol for the legend. `bars` are in prices, `scale` puts them on
+/// the axis.
+#[allow(clippy::too_many_arguments)]
+fn plot_prices(
+    plot_ui: &mut PlotUi,
+    name: &str,
+    chart_type: ChartType,
+    bars: &[Candle],
+    time_step: f64,
+    style: CandleStyle,
+    box_size: f64,
+    scale: YScale,
+) {
+    if bars.is_empty() {
+        return;
+    }
+    if chart_type == ChartType::PointAndFigure {
+        // the boxes are a price size, so they're laid out before scaling
+        plot_point_and_figure(bars, plot_ui, name, box_size, style, scale);
         return;
     }
 
+    let bars: Vec<Candle> = bars.iter().map(|bar| scale.candle(bar)).collect();
+    let bar_width = (time_step * 0.6).max(1.0);
+    match chart_type {
+        ChartType::Candles | ChartType::HeikinAshi => plot_candle(&bars, plot_ui, name, bar_width, style),
+        ChartType::Renko => {
+            // several bricks can share a bar
+            let spacing = bars.windows(2).map(|pair| pair[1].x - pair[0].x).filter(|&gap| gap > 0.0).fold(time_step, f64::min);
+            plot_candle(&bars, plot_ui, name, (spacing * 0.8).max(1.0), style);
+        }
+        ChartType::OhlcBars => plot_ohlc_bars(&bars, plot_ui, name, bar_width, style),
+        ChartType::Line => plot_line(&bars, plot_ui, name),
+        ChartType::Area => plot_area(&bars, plot_ui, name),
+        ChartType::Baseline => plot_baseline(&bars, plot_ui, name, style),
+        ChartType::PointAndFigure => {}
+    }
+}
+
+fn plot_line(points: &[Candle], plot_ui: &mut PlotUi, name: &str) {
     let line_points: PlotPoints = points
         .iter()
-        .map(|point| [timestamp_to_f64(&point.timestamp), point.close as f64])
+        .map(|point| [point.x, point.close])
         .collect();
 
-    let line = Line::new("LINE", line_points);
+    let line = Line::new(name, line_points).allow_hover(false);
     plot_ui.line(line);
 }
 
-fn plot_candle(points: &[Point], plot_ui: &mut PlotUi, time_step: f64) {
-    if points.is_empty() {
-        return;
-    }
+/// The line filled down to the lowest low.
+fn plot_area(points: &[Candle], plot_ui: &mut PlotUi, name: &str) {
+    let floor = points.iter().map(|point| point.low).fold(f64::INFINITY, f64::min);
+    let line_points: PlotPoints = points.iter().map(|point| [point.x, point.close]).collect();
+    let color = Color32::from_rgb(0, 150, 255);
+    plot_ui.line(Line::new(name, line_points).color(color).fill(floor as f32).fill_alpha(0.25).allow_hover(false));
+}
 
-    let candle_width = (time_step * 0.6).max(1.0);
-    let whisker_width = (candle_width * 0.4).max(1.0);
-    let mut box_elements = Vec::with_capacity(points.len());
-    let mut previous_close: Option<f64> = None;
+/// The line filled up to the first close where it's above it, and down where it's below.
+fn plot_baseline(points: &[Candle], plot_ui: &mut PlotUi, name: &str, style: CandleStyle) {
+    let baseline = points[0].close;
+    for (color, clamp) in [(style.up, f64::max as fn(f64, f64) -> f64), (style.down, f64::min)] {
+        let line_points: PlotPoints = points.iter().map(|point| [point.x, clamp(point.close, baseline)]).collect();
+        plot_ui.line(Line::new(name, line_points).color(color).fill(baseline as f32).fill_alpha(0.2).allow_hover(false));
+    }
+    plot_ui.hline(HLine::new("", baseline).color(Color32::GRAY).style(LineStyle::dashed_loose()));
+}
 
+/// High to low with the open ticked on the left and the close on the right.
+fn plot_ohlc_bars(points: &[Candle], plot_ui: &mut PlotUi, name: &str, bar_width: f64, style: CandleStyle) {
+    let mut bounds = PlotBounds::NOTHING;
     for point in points {
-        let spread = BoxSpread::new(point.low, point.open, point.close, point.close, point.high);
-        let mut color = Color32::LIGHT_GREEN;
+        bounds.extend_with(&PlotPoint::new(point.x - bar_width / 2.0, point.low));
+        bounds.extend_with(&PlotPoint::new(point.x + bar_width / 2.0, point.high));
+    }
+    plot_ui.add(OhlcBars { base: PlotItemBase::new(name.to_owned()), bars: points.to_vec(), tick: bar_width / 2.0, style, bounds });
+}
 
-        if let Some(last_close) = previous_close {
-            if last_close > point.close {
-                color = Color32::LIGHT_RED;
-            }
+/// All the OHLC bars as one plot item, a `Line` each gets slow with a few thousand of them.
+struct OhlcBars {
+    base: PlotItemBase,
+    bars: Vec<Candle>,
+    tick: f64,
+    style: CandleStyle,
+    bounds: PlotBounds,
+}
+
+impl PlotItem for OhlcBars {
+    fn shapes(&self, _ui: &egui::Ui, transform: &PlotTransform, shapes: &mut Vec<egui::Shape>) {
+        let view = transform.bounds();
+        let visible = self.bars.iter().filter(|bar| bar.x + self.tick >= view.min()[0] && bar.x - self.tick <= view.max()[0]);
+        for bar in visible {
+            let stroke = Stroke::new(1.5, if bar.close >= bar.open { self.style.up } else { self.style.down });
+            let at = |x, y| transform.position_from_point(&PlotPoint::new(x, y));
+            shapes.push(egui::Shape::line_segment([at(bar.x, bar.low), at(bar.x, bar.high)], stroke));
+            shapes.push(egui::Shape::line_segment([at(bar.x - self.tick, bar.open), at(bar.x, bar.open)], stroke));
+            shapes.push(egui::Shape::line_segment([at(bar.x, bar.close), at(bar.x + self.tick, bar.close)], stroke));
         }
+    }
 
-        previous_close = Some(point.close);
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
 
-        box_elements.push(
-            BoxElem::new(timestamp_to_f64(&point.timestamp), spread)
-                .box_width(candle_width)
-                .stroke(Stroke::new(2., color))
-                .whisker_width(whisker_width)
-                .fill(color),
-        );
+    fn color(&self) -> Color32 {
+        self.style.up
+    }
+
+    // the chart shows its own tooltip for the bar under the pointer
+    fn allow_hover(&self) -> bool {
+        false
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::None
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        self.bounds
     }
-    let formatter = Box::new(|elem: &BoxElem, _plot: &BoxPlot| {
-        let spread = &elem.spread;
-        format!(
-            "Open: {open:.2}\nClose: {close:.2}\nLow: {low:.2}\nHigh: {high:.2}",
-            open = if &elem.fill == &Color32::LIGHT_RED { spread.quartile3 } else { spread.quartile1 },
-            close = if &elem.fill == &Color32::LIGHT_RED { spread.quartile1 } else { spread.quartile3 },
-            low = spread.lower_whisker,
-            high = spread.upper_whisker,
-        )
-    });
 
-    let box_plot = 
-        BoxPlot::new("CANDLE", box_elements)
-            .element_formatter(formatter);
-    plot_ui.box_plot(box_plot);
+    fn base(&self) -> &PlotItemBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PlotItemBase {
+        &mut self.base
+    }
 }
 
+/// An X for every box of a rising column, an O for a falling one.
+fn plot_point_and_figure(columns: &[Candle], plot_ui: &mut PlotUi, name: &str, box_size: f64, style: CandleStyle, scale: YScale) {
+    let mut rising = Vec::new();
+    let mut falling = Vec::new();
+    let mut room = charts::MAX_BOXES;
+    for column in columns {
+        let boxes = charts::column_boxes(column, box_size).min(room);
+        room -= boxes;
+        let marks = if column.close >= column.open { &mut rising } else { &mut falling };
+        marks.extend((0..boxes).map(|index| [column.x, scale.to_plot(column.low + index as f64 * box_size)]));
+    }
+    plot_ui.points(Points::new(name, rising).shape(MarkerShape::Cross).radius(4.0).color(style.up).allow_hover(false));
+    plot_ui.points(Points::new(name, falling).shape(MarkerShape::Circle).filled(false).radius(4.0).color(style.down).allow_hover(false));
+}
 
-//TODO: Enhance the BoxElem so tht it has better tooltips
+fn plot_candle(points: &[Candle], plot_ui: &mut PlotUi, name: &str, candle_width: f64, style: CandleStyle) {
+    let box_elements: Vec<BoxElem> = points
+        .iter()
+        .map(|point| {
+            let rising = point.close >= point.open;
+            let color = if rising { style.up } else { style.down };
+            // the median sits on the close, which is always one of the body's edges
+            let spread = BoxSpread::new(point.low, point.open.min(point.close), point.close, point.open.max(point.close), point.high);
+            BoxElem::new(point.x, spread)
+                .box_width(candle_width)
+                .whisker_width(0.0)
+                .stroke(Stroke::new(1.5, color))
+                .fill(if rising && style.hollow { Color32::TRANSPARENT } else { color })
+        })
+        .collect();
+
+    // the chart shows its own tooltip for the bar under the pointer
+    plot_ui.box_plot(BoxPlot::new(name, box_elements).allow_hover(false));
+}
+
+/// Date, OHLC, change from the previous close (the open for the first bar) and volume.
+fn candle_tooltip(candle: &Candle, previous_close: Option<f64>, timeframe: Timeframe) -> String {
+    format!(
+        "{}\nOpen: {:.2}\nHigh: {:.2}\nLow: {:.2}\nClose: {:.2}\nChange: {}\nVolume: {}",
+        format_bar_time(candle.time, timeframe),
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        format_change(candle.close, previous_close.unwrap_or(candle.open)),
+        format_volume(candle.volume.max(0.0) as u64),
+    )
+}
+
+fn format_bar_time(time: DateTime<Utc>, timeframe: Timeframe) -> String {
+    let format = if timeframe.is_intraday() { "%Y-%m-%d %H:%M" } else { "%Y-%m-%d" };
+    time.format(format).to_string()
+}
+
+/// e.g. "+1.25 (+0.83%)"
+fn format_change(close: f64, reference: f64) -> String {
+    let change = close - reference;
+    let change_percent = if reference != 0.0 { change / reference * 100.0 } else { 0.0 };
+    format!("{change:+.2} ({change_percent:+.2}%)")
+}
@@ -0,0 +1,727 @@
+use std::{
+    collections::BTreeSet,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use egui::Color32;
+use rusty_trading_model::structs::Point;
+
+use crate::backend::{self, BackendProfile};
+
+/// Upper bound for the reconnect backoff.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// WebSocket attempts that never got to open before we try SSE instead.
+const WEBSOCKET_ATTEMPTS_BEFORE_FALLBACK: u32 = 2;
+/// How long after falling back to SSE we try WebSocket again, doubling each time it still
+/// doesn't open, up to the max.
+const WEBSOCKET_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_WEBSOCKET_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+/// Pushed by the server. A `bar` opens or updates a bar, a `tick` moves the last bar's close.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedMessage {
+    Bar { symbol: String, point: Point },
+    Tick { symbol: String, price: f64, timestamp: DateTime<Utc> },
+}
+
+/// Sent to the server over WebSocket. SSE gets the symbols in the URL instead.
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FeedCommand<'a> {
+    Subscribe { symbols: Vec<&'a str> },
+    Unsubscribe { symbols: Vec<&'a str> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    WebSocket,
+    Sse,
+}
+
+impl Transport {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Transport::WebSocket => "WS",
+            Transport::Sse => "SSE",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedState {
+    Off,
+    Connecting(Transport),
+    Live(Transport),
+    /// Waiting for the backoff to run out before the next attempt.
+    Reconnecting,
+}
+
+impl FeedState {
+    pub fn label(&self) -> String {
+        match self {
+            FeedState::Off => "Polling".to_owned(),
+            FeedState::Connecting(transport) => format!("Connecting ({})", transport.label()),
+            FeedState::Live(transport) => format!("Live ({})", transport.label()),
+            FeedState::Reconnecting => "Reconnecting".to_owned(),
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            FeedState::Live(_) => Color32::from_rgb(0, 255, 0),
+            FeedState::Reconnecting => Color32::from_rgb(255, 165, 0),
+            _ => Color32::GRAY,
+        }
+    }
+}
+
+/// What happened since the last call to [`MarketFeed::update`].
+#[derive(Default)]
+pub struct FeedUpdate {
+    pub messages: Vec<FeedMessage>,
+    /// The feed (re)connected, anything that happened while it was down has to be polled.
+    pub reconnected: bool,
+}
+
+enum LinkStatus {
+    Connecting,
+    Open,
+    Closed(String),
+}
+
+/// Written by the transport, drained by the UI thread.
+struct Inbox {
+    status: LinkStatus,
+    messages: Vec<FeedMessage>,
+}
+
+impl Inbox {
+    fn push_text(&mut self, text: &str) {
+        match serde_json::from_str(text) {
+            Ok(message) => self.messages.push(message),
+            Err(e) => log::warn!("Ignoring feed message {text:?}: {e}"),
+        }
+    }
+}
+
+enum LinkHandle {
+    #[cfg(not(target_arch = "wasm32"))]
+    NativeWebSocket(std::sync::mpsc::Sender<native::Outgoing>),
+    // `None` if the browser refused to even create the socket
+    #[cfg(target_arch = "wasm32")]
+    WebWebSocket(Option<web_sys::WebSocket>),
+    Sse(Arc<AtomicBool>),
+}
+
+/// One connection attempt.
+struct Link {
+    transport: Transport,
+    inbox: Arc<Mutex<Inbox>>,
+    handle: LinkHandle,
+    /// What the server knows we're subscribed to.
+    subscribed: BTreeSet<String>,
+    was_open: bool,
+}
+
+impl Link {
+    fn send(&self, command: &FeedCommand<'_>) {
+        let Ok(text) = serde_json::to_string(command) else {
+            return;
+        };
+        match &self.handle {
+            #[cfg(not(target_arch = "wasm32"))]
+            LinkHandle::NativeWebSocket(sender) => {
+                let _ = sender.send(native::Outgoing::Text(text));
+            }
+            #[cfg(target_arch = "wasm32")]
+            LinkHandle::WebWebSocket(socket) => {
+                if let Some(Err(e)) = socket.as_ref().map(|socket| socket.send_with_str(&text)) {
+                    log::warn!("Failed to send feed command: {e:?}");
+                }
+            }
+            LinkHandle::Sse(_) => {}
+        }
+    }
+
+    fn close(&self) {
+        match &self.handle {
+            #[cfg(not(target_arch = "wasm32"))]
+            LinkHandle::NativeWebSocket(sender) => {
+                let _ = sender.send(native::Outgoing::Close);
+            }
+            #[cfg(target_arch = "wasm32")]
+            LinkHandle::WebWebSocket(socket) => {
+                if let Some(socket) = socket {
+                    let _ = socket.close();
+                }
+            }
+            // ehttp can't abort, the stream stops at the next chunk
+            LinkHandle::Sse(closed) => closed.store(true, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Streaming market data: keeps one WebSocket (or SSE, as a fallback) connection to the
+/// backend, subscribed to whatever symbols are on screen, and reconnects with backoff. While
+/// on SSE, WebSocket is tried again now and then and takes over once it opens.
+/// Driven from the UI thread by calling [`MarketFeed::update`] every frame.
+pub struct MarketFeed {
+    link: Option<Link>,
+    transport: Transport,
+    failures: u32,
+    retry_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    // a WebSocket being tried alongside the SSE stream
+    probe: Option<Link>,
+    probe_failures: u32,
+    probe_at: Option<DateTime<Utc>>,
+}
+
+impl Default for MarketFeed {
+    fn default() -> Self {
+        Self {
+            link: None,
+            transport: Transport::WebSocket,
+            failures: 0,
+            retry_at: None,
+            last_error: None,
+            probe: None,
+            probe_failures: 0,
+            probe_at: None,
+        }
+    }
+}
+
+impl Drop for MarketFeed {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+impl MarketFeed {
+    pub fn state(&self) -> FeedState {
+        match &self.link {
+            Some(link) if link.was_open => FeedState::Live(link.transport),
+            Some(link) => FeedState::Connecting(link.transport),
+            None if self.retry_at.is_some() => FeedState::Reconnecting,
+            None => FeedState::Off,
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self.state(), FeedState::Live(_))
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Drops the connection and starts over with WebSocket, e.g. after the profile changed.
+    pub fn reset(&mut self) {
+        self.disconnect();
+        *self = Self::default();
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(link) = self.link.take() {
+            link.close();
+        }
+        if let Some(probe) = self.probe.take() {
+            probe.close();
+            // otherwise nothing would try WebSocket again
+            self.probe_at = Some(Utc::now() + websocket_retry_delay(self.probe_failures));
+        }
+    }
+
+    /// Connects, reconnects, keeps the subscriptions in line with `symbols` and hands back
+    /// whatever arrived since the last frame.
+    pub fn update(&mut self, symbols: &BTreeSet<String>, profile: &BackendProfile, ctx: &egui::Context) -> FeedUpdate {
+        let mut update = FeedUpdate::default();
+
+        if !profile.streaming || symbols.is_empty() {
+            self.disconnect();
+            self.retry_at = None;
+            return update;
+        }
+
+        self.update_probe(symbols, profile, ctx);
+
+        // Collect what the transport has for us
+        let mut closed = None;
+        if let Some(link) = &mut self.link {
+            let mut inbox = link.inbox.lock().unwrap();
+            update.messages = std::mem::take(&mut inbox.messages);
+            match &inbox.status {
+                LinkStatus::Connecting => {}
+                LinkStatus::Open => {
+                    if !link.was_open {
+                        link.was_open = true;
+                        update.reconnected = true;
+                        self.last_error = None;
+                        log::info!("Market data feed connected over {}", link.transport.label());
+                    }
+                }
+                LinkStatus::Closed(reason) => closed = Some(reason.clone()),
+            }
+        }
+
+        if let Some(reason) = closed {
+            self.handle_closed(reason);
+        }
+
+        let now = Utc::now();
+        if self.link.is_none() && self.retry_at.is_none_or(|retry_at| retry_at <= now) {
+            self.retry_at = None;
+            self.link = Some(connect(self.transport, symbols, profile, ctx));
+        }
+
+        if let Some(link) = self.link.as_mut().filter(|link| link.was_open && &link.subscribed != symbols) {
+            match link.transport {
+                Transport::WebSocket => {
+                    let added: Vec<&str> = symbols.difference(&link.subscribed).map(String::as_str).collect();
+                    let removed: Vec<&str> = link.subscribed.difference(symbols).map(String::as_str).collect();
+                    if !added.is_empty() {
+                        link.send(&FeedCommand::Subscribe { symbols: added });
+                    }
+                    if !removed.is_empty() {
+                        link.send(&FeedCommand::Unsubscribe { symbols: removed });
+                    }
+                    link.subscribed = symbols.clone();
+                }
+                // the symbols are baked into the URL, so start a new stream. A WebSocket probe
+                // keeps going, it gets the new symbols if it takes over
+                Transport::Sse => {
+                    link.close();
+                    self.link = Some(connect(Transport::Sse, symbols, profile, ctx));
+                }
+            }
+        }
+
+        if let Some(retry_at) = self.retry_at.or(self.probe_at) {
+            ctx.request_repaint_after((retry_at - now).to_std().unwrap_or_default());
+        }
+
+        update
+    }
+
+    /// Tries WebSocket again while we're on SSE, and switches over once it opens.
+    fn update_probe(&mut self, symbols: &BTreeSet<String>, profile: &BackendProfile, ctx: &egui::Context) {
+        if self.transport != Transport::Sse {
+            return;
+        }
+        let now = Utc::now();
+        let Some(probe) = &self.probe else {
+            if self.probe_at.is_some_and(|probe_at| probe_at <= now) {
+                self.probe_at = None;
+                self.probe = Some(connect(Transport::WebSocket, symbols, profile, ctx));
+            }
+            return;
+        };
+
+        let closed = match &probe.inbox.lock().unwrap().status {
+            LinkStatus::Connecting => return,
+            LinkStatus::Open => None,
+            LinkStatus::Closed(reason) => Some(reason.clone()),
+        };
+        match closed {
+            // left as not open yet, so `update` sees it connect and repolls
+            None => {
+                log::info!("WebSocket feed is back, leaving SSE");
+                if let Some(link) = self.link.take() {
+                    link.close();
+                }
+                self.link = self.probe.take();
+                self.transport = Transport::WebSocket;
+                self.failures = 0;
+                self.retry_at = None;
+                self.probe_failures = 0;
+            }
+            Some(reason) => {
+                log::debug!("WebSocket feed still unavailable: {reason}");
+                self.probe = None;
+                self.probe_failures += 1;
+                self.probe_at = Some(now + websocket_retry_delay(self.probe_failures));
+            }
+        }
+    }
+
+    fn handle_closed(&mut self, reason: String) {
+        let Some(link) = self.link.take() else {
+            return;
+        };
+        log::warn!("Market data feed ({}) closed: {reason}", link.transport.label());
+        self.last_error = Some(reason);
+
+        if link.was_open {
+            self.failures = 0;
+        } else {
+            self.failures += 1;
+            if self.transport == Transport::WebSocket && self.failures >= WEBSOCKET_ATTEMPTS_BEFORE_FALLBACK {
+                log::info!("WebSocket feed unavailable, falling back to SSE");
+                self.transport = Transport::Sse;
+                self.failures = 0;
+                self.probe_failures = 0;
+                self.probe_at = Some(Utc::now() + websocket_retry_delay(0));
+            }
+        }
+
+        let delay = (Duration::from_secs(1) * 2u32.pow(self.failures.min(5))).min(MAX_RECONNECT_DELAY);
+        self.retry_at = Some(Utc::now() + delay);
+    }
+}
+
+fn websocket_retry_delay(failures: u32) -> Duration {
+    (WEBSOCKET_RETRY_DELAY * 2u32.pow(failures.min(5))).min(MAX_WEBSOCKET_RETRY_DELAY)
+}
+
+fn connect(transport: Transport, symbols: &BTreeSet<String>, profile: &BackendProfile, ctx: &egui::Context) -> Link {
+    let inbox = Arc::new(Mutex::new(Inbox {
+        status: LinkStatus::Connecting,
+        messages: Vec::new(),
+    }));
+
+    let handle = match transport {
+        Transport::WebSocket => {
+            let url = profile.websocket_url();
+            log::info!("Connecting market data feed to {url}");
+            connect_websocket(url, profile, symbols, Arc::clone(&inbox), ctx.clone())
+        }
+        Transport::Sse => {
+            let closed = Arc::new(AtomicBool::new(false));
+            let joined = symbols.iter().map(|symbol| backend::encode_query(symbol)).collect::<Vec<_>>().join(",");
+            let request = profile.get(&format!("{}?symbols={joined}", profile.sse_path));
+            log::info!("Connecting market data feed to {}", request.url);
+            connect_sse(request, Arc::clone(&closed), Arc::clone(&inbox), ctx.clone());
+            LinkHandle::Sse(closed)
+        }
+    };
+
+    Link {
+        transport,
+        inbox,
+        handle,
+        subscribed: symbols.clone(),
+        was_open: false,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn connect_websocket(url: String, profile: &BackendProfile, symbols: &BTreeSet<String>, inbox: Arc<Mutex<Inbox>>, ctx: egui::Context) -> LinkHandle {
+    LinkHandle::NativeWebSocket(native::connect(url, profile, symbols, inbox, ctx))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn connect_websocket(url: String, _profile: &BackendProfile, symbols: &BTreeSet<String>, inbox: Arc<Mutex<Inbox>>, ctx: egui::Context) -> LinkHandle {
+    LinkHandle::WebWebSocket(web::connect(&url, symbols, inbox, ctx))
+}
+
+/// Server-sent events over ehttp's streaming fetch. We only care about `data:` lines.
+fn connect_sse(request: ehttp::Request, closed: Arc<AtomicBool>, inbox: Arc<Mutex<Inbox>>, ctx: egui::Context) {
+    let buffer = Mutex::new(String::new());
+    ehttp::streaming::fetch(request, move |part| {
+        if closed.load(Ordering::Relaxed) {
+            return ControlFlow::Break(());
+        }
+        let mut inbox = inbox.lock().unwrap();
+        let flow = match part {
+            Err(e) => {
+                inbox.status = LinkStatus::Closed(e);
+                ControlFlow::Break(())
+            }
+            Ok(ehttp::streaming::Part::Response(response)) if !response.ok => {
+                inbox.status = LinkStatus::Closed(format!("HTTP {}: {}", response.status, response.status_text));
+                ControlFlow::Break(())
+            }
+            Ok(ehttp::streaming::Part::Response(_)) => {
+                inbox.status = LinkStatus::Open;
+                ControlFlow::Continue(())
+            }
+            Ok(ehttp::streaming::Part::Chunk(chunk)) if chunk.is_empty() => {
+                inbox.status = LinkStatus::Closed("stream ended".to_owned());
+                ControlFlow::Break(())
+            }
+            Ok(ehttp::streaming::Part::Chunk(chunk)) => {
+                let mut buffer = buffer.lock().unwrap();
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+                while let Some(end) = buffer.find('\n') {
+                    let line: String = buffer.drain(..=end).collect();
+                    if let Some(data) = line.trim_end().strip_prefix("data:") {
+                        inbox.push_text(data.trim_start());
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+        };
+        ctx.request_repaint();
+        flow
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{
+        collections::BTreeSet,
+        net::{TcpStream, ToSocketAddrs},
+        sync::{mpsc, Arc, Mutex},
+        time::Duration,
+    };
+
+    use tungstenite::{client::IntoClientRequest, http::HeaderValue, HandshakeError, Message};
+
+    use super::{FeedCommand, Inbox, LinkStatus};
+    use crate::backend::BackendProfile;
+
+    /// How long a read blocks before we look at the outgoing queue again.
+    const POLL_OUTGOING: Duration = Duration::from_millis(50);
+
+    pub enum Outgoing {
+        Text(String),
+        Close,
+    }
+
+    /// Runs the socket on its own thread. Only plain `ws://` is supported, there is no TLS
+    /// backend compiled in; use the SSE fallback for `https` backends.
+    pub fn connect(
+        url: String,
+        profile: &BackendProfile,
+        symbols: &BTreeSet<String>,
+        inbox: Arc<Mutex<Inbox>>,
+        ctx: egui::Context,
+    ) -> mpsc::Sender<Outgoing> {
+        let (sender, receiver) = mpsc::channel();
+        let auth = profile.auth_header();
+        let timeout = profile.timeout();
+        let subscribe = serde_json::to_string(&FeedCommand::Subscribe {
+            symbols: symbols.iter().map(String::as_str).collect(),
+        })
+        .unwrap_or_default();
+
+        std::thread::spawn(move || {
+            let reason = match run(&url, auth, timeout, subscribe, &receiver, &inbox, &ctx) {
+                Ok(()) => "closed".to_owned(),
+                Err(e) => e,
+            };
+            inbox.lock().unwrap().status = LinkStatus::Closed(reason);
+            ctx.request_repaint();
+        });
+
+        sender
+    }
+
+    /// `timeout` bounds connecting and the handshake, an unreachable host mustn't hang the
+    /// thread.
+    fn run(
+        url: &str,
+        auth: Option<(String, String)>,
+        timeout: Duration,
+        subscribe: String,
+        receiver: &mpsc::Receiver<Outgoing>,
+        inbox: &Arc<Mutex<Inbox>>,
+        ctx: &egui::Context,
+    ) -> Result<(), String> {
+        let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+        if let Some((name, value)) = auth {
+            let name: tungstenite::http::HeaderName = name.parse().map_err(|e| format!("bad auth header: {e}"))?;
+            let value = HeaderValue::from_str(&value).map_err(|e| format!("bad auth header: {e}"))?;
+            request.headers_mut().insert(name, value);
+        }
+        let uri = request.uri();
+        if uri.scheme_str() != Some("ws") {
+            return Err(format!("{url}: only ws:// is supported natively"));
+        }
+        let host = uri.host().unwrap_or_default().to_owned();
+        let port = uri.port_u16().unwrap_or(80);
+
+        let stream = connect_timeout(&host, port, timeout)?;
+        stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+        let (mut socket, _) = tungstenite::client(request, stream).map_err(|e| match e {
+            HandshakeError::Failure(e) => e.to_string(),
+            HandshakeError::Interrupted(_) => "handshake interrupted".to_owned(),
+        })?;
+        socket.get_ref().set_read_timeout(Some(POLL_OUTGOING)).map_err(|e| e.to_string())?;
+
+        socket.send(Message::text(subscribe)).map_err(|e| e.to_string())?;
+        inbox.lock().unwrap().status = LinkStatus::Open;
+        ctx.request_repaint();
+
+        loop {
+            while let Ok(outgoing) = receiver.try_recv() {
+                match outgoing {
+                    Outgoing::Text(text) => socket.send(Message::text(text)).map_err(|e| e.to_string())?,
+                    Outgoing::Close => {
+                        let _ = socket.close(None);
+                        return Ok(());
+                    }
+                }
+            }
+
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    inbox.lock().unwrap().push_text(&text);
+                    ctx.request_repaint();
+                }
+                Ok(Message::Close(frame)) => {
+                    return Err(frame.map_or_else(|| "server closed the connection".to_owned(), |frame| frame.reason.to_string()));
+                }
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    /// Tries each address the host resolves to in turn, giving each `timeout`.
+    fn connect_timeout(host: &str, port: u16, timeout: Duration) -> Result<TcpStream, String> {
+        let mut last_error = format!("{host} didn't resolve");
+        for address in (host, port).to_socket_addrs().map_err(|e| e.to_string())? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
+
+    use eframe::wasm_bindgen::{closure::Closure, JsCast as _};
+
+    use super::{FeedCommand, Inbox, LinkStatus};
+
+    /// Browsers can't set headers on a WebSocket, so there's no auth here; put a token in the
+    /// path if the server needs one.
+    pub fn connect(url: &str, symbols: &BTreeSet<String>, inbox: Arc<Mutex<Inbox>>, ctx: egui::Context) -> Option<web_sys::WebSocket> {
+        let socket = match web_sys::WebSocket::new(url) {
+            Ok(socket) => socket,
+            Err(e) => {
+                inbox.lock().unwrap().status = LinkStatus::Closed(format!("{e:?}"));
+                return None;
+            }
+        };
+
+        let subscribe = serde_json::to_string(&FeedCommand::Subscribe {
+            symbols: symbols.iter().map(String::as_str).collect(),
+        })
+        .unwrap_or_default();
+
+        {
+            let inbox = Arc::clone(&inbox);
+            let ctx = ctx.clone();
+            let opened = socket.clone();
+            let on_open = Closure::<dyn FnMut()>::new(move || {
+                let _ = opened.send_with_str(&subscribe);
+                inbox.lock().unwrap().status = LinkStatus::Open;
+                ctx.request_repaint();
+            });
+            socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+            on_open.forget();
+        }
+
+        {
+            let inbox = Arc::clone(&inbox);
+            let ctx = ctx.clone();
+            let on_message = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MessageEvent| {
+                if let Some(text) = event.data().as_string() {
+                    inbox.lock().unwrap().push_text(&text);
+                    ctx.request_repaint();
+                }
+            });
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            on_message.forget();
+        }
+
+        {
+            let inbox = Arc::clone(&inbox);
+            let ctx = ctx.clone();
+            let on_close = Closure::<dyn FnMut(_)>::new(move |event: web_sys::CloseEvent| {
+                let reason = match event.reason() {
+                    reason if reason.is_empty() => format!("closed with code {}", event.code()),
+                    reason => reason,
+                };
+                inbox.lock().unwrap().status = LinkStatus::Closed(reason);
+                ctx.request_repaint();
+            });
+            socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+            on_close.forget();
+        }
+
+        Some(socket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // stands in for either transport, closing it only sets the flag
+    fn link(transport: Transport, status: LinkStatus, symbols: &BTreeSet<String>) -> (Link, Arc<AtomicBool>) {
+        let closed = Arc::new(AtomicBool::new(false));
+        let link = Link {
+            transport,
+            inbox: Arc::new(Mutex::new(Inbox { status, messages: Vec::new() })),
+            handle: LinkHandle::Sse(Arc::clone(&closed)),
+            subscribed: symbols.clone(),
+            was_open: matches!(transport, Transport::Sse),
+        };
+        (link, closed)
+    }
+
+    fn symbols(symbols: &[&str]) -> BTreeSet<String> {
+        symbols.iter().map(|symbol| (*symbol).to_owned()).collect()
+    }
+
+    /// On SSE with a WebSocket probe still connecting.
+    fn probing(subscribed: &BTreeSet<String>) -> (MarketFeed, Arc<AtomicBool>, Arc<AtomicBool>) {
+        let (sse, sse_closed) = link(Transport::Sse, LinkStatus::Open, subscribed);
+        let (probe, probe_closed) = link(Transport::WebSocket, LinkStatus::Connecting, subscribed);
+        let mut feed = MarketFeed::default();
+        feed.link = Some(sse);
+        feed.transport = Transport::Sse;
+        feed.probe = Some(probe);
+        (feed, sse_closed, probe_closed)
+    }
+
+    fn profile() -> BackendProfile {
+        // nothing listens there, the new SSE stream just fails in the background
+        let mut profile = BackendProfile::default();
+        profile.base_url = "http://127.0.0.1:9".to_owned();
+        profile.streaming = true;
+        profile
+    }
+
+    #[test]
+    fn new_symbols_on_sse_keep_the_websocket_probe() {
+        let (mut feed, sse_closed, probe_closed) = probing(&symbols(&["AAPL"]));
+        feed.update(&symbols(&["AAPL", "MSFT"]), &profile(), &egui::Context::default());
+
+        assert!(sse_closed.load(Ordering::Relaxed));
+        assert!(!probe_closed.load(Ordering::Relaxed));
+        assert!(feed.probe.is_some());
+        assert_eq!(feed.link.as_ref().map(|link| &link.subscribed), Some(&symbols(&["AAPL", "MSFT"])));
+    }
+
+    #[test]
+    fn a_dropped_probe_is_tried_again_later() {
+        let (mut feed, _, probe_closed) = probing(&symbols(&["AAPL"]));
+        feed.update(&BTreeSet::new(), &profile(), &egui::Context::default());
+
+        assert!(probe_closed.load(Ordering::Relaxed));
+        assert!(feed.probe.is_none());
+        assert!(feed.probe_at.is_some_and(|probe_at| probe_at > Utc::now()));
+        assert_eq!(feed.transport, Transport::Sse);
+    }
+}
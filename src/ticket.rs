@@ -0,0 +1,548 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Color32, RichText};
+
+use crate::{
+    client::TradingBackend,
+    orders::{self, BracketSpec, LevelKind, OrderBook, OrderSpec, OrderStatus, OrderType, Side, TimeInForce},
+    portfolio::Portfolio,
+    price::{InstrumentSpec, Instruments, Price},
+    risk::{Exposure, RiskLimits},
+};
+
+/// What a ticket needs from the app to check and send orders.
+pub struct TradingContext<'a> {
+    pub backend: &'a dyn TradingBackend,
+    pub orders: &'a Arc<Mutex<OrderBook>>,
+    pub risk: &'a RiskLimits,
+    pub portfolio: &'a Portfolio,
+    pub instruments: &'a Instruments,
+}
+
+impl TradingContext<'_> {
+    fn exposure(&self, symbol: &str, last_price: Option<Price>) -> Exposure {
+        Exposure {
+            position: self.portfolio.position(symbol).map_or(0, |position| position.qty),
+            last_price,
+            daily_pnl: self.portfolio.daily_pnl(),
+        }
+    }
+}
+
+/// The order form behind a pair of BUY/SELL buttons, from validation through the
+/// confirmation dialog to the order's status. Used by the stock windows and Quick Trade.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct OrderTicket {
+    pub qty: String,
+    // the limit price
+    pub price: String,
+    pub stop_price: String,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    // send take-profit and stop-loss exits as the order fills
+    pub bracket: bool,
+    pub take_profit: String,
+    pub stop_loss: String,
+    // last price of the symbol on its tick, what a market order is expected to fill at
+    #[serde(skip)]
+    market_price: Option<Price>,
+    #[serde(skip)]
+    side: Side,
+    // what the confirmation dialog is showing
+    #[serde(skip)]
+    pending: Option<(OrderSpec, Option<BracketSpec>)>,
+    // whether `pending` was made from the form, which gets cleared once it's accepted
+    #[serde(skip)]
+    pending_from_form: bool,
+    // why the last BUY/SELL click didn't get to the confirmation
+    #[serde(skip)]
+    invalid: Vec<String>,
+    // client id of the last order sent from here, the form stays until it's accepted
+    #[serde(skip)]
+    submitted: Option<String>,
+    #[serde(skip)]
+    awaiting_ack: bool,
+}
+
+impl OrderTicket {
+    /// Quantity, order type and time in force, then whichever prices the type needs.
+    /// `market_price` is the symbol's last price, if we have one.
+    pub fn show_form(
+        &mut self,
+        ui: &mut egui::Ui,
+        id_salt: impl std::hash::Hash,
+        market_price: Option<f64>,
+        instrument: InstrumentSpec,
+    ) {
+        self.set_market_price(market_price, instrument);
+        let market_price = self.market_price;
+        let id = ui.id().with(id_salt);
+
+        ui.horizontal(|ui| {
+            ui.label("Qty:");
+            ui.add(egui::TextEdit::singleline(&mut self.qty).desired_width(60.0));
+            egui::ComboBox::from_id_salt(id.with("order_type"))
+                .selected_text(self.order_type.label())
+                .width(90.0)
+                .show_ui(ui, |ui| {
+                    for order_type in OrderType::ALL {
+                        ui.selectable_value(&mut self.order_type, order_type, order_type.label());
+                    }
+                });
+            egui::ComboBox::from_id_salt(id.with("time_in_force"))
+                .selected_text(self.time_in_force.label())
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for time_in_force in TimeInForce::ALL {
+                        ui.selectable_value(&mut self.time_in_force, time_in_force, time_in_force.label())
+                            .on_hover_text(time_in_force.description());
+                    }
+                })
+                .response
+                .on_hover_text(self.time_in_force.description());
+        });
+
+        if self.order_type.has_stop() {
+            ui.horizontal(|ui| {
+                ui.label("Stop:");
+                ui.add(egui::TextEdit::singleline(&mut self.stop_price).desired_width(80.0));
+                self.show_last_price_button(ui, Field::Stop);
+            });
+        }
+        if self.order_type.has_limit() {
+            ui.horizontal(|ui| {
+                ui.label("Limit:");
+                ui.add(egui::TextEdit::singleline(&mut self.price).desired_width(80.0));
+                self.show_last_price_button(ui, Field::Limit);
+            });
+        }
+        if self.order_type == OrderType::Market {
+            let estimate = match market_price {
+                Some(price) => format!("Fills at the market, last ${price}"),
+                None => "Fills at the market".to_owned(),
+            };
+            ui.label(RichText::new(estimate).small().color(Color32::GRAY));
+        }
+    }
+
+    /// The symbol's last price, put on its tick. `show_form` does this for tickets with a form.
+    pub fn set_market_price(&mut self, market_price: Option<f64>, instrument: InstrumentSpec) {
+        self.market_price = market_price.map(|price| Price::from_f64(price).round_to(instrument.tick_size));
+    }
+
+    /// The bracket toggle and its exit levels. Only the stock windows offer brackets.
+    pub fn show_bracket_fields(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.bracket, "🎯 Bracket")
+            .on_hover_text("Send a take-profit and a stop-loss as the order fills, a fill on one shrinks the other");
+        if !self.bracket {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("TP:");
+            ui.add(egui::TextEdit::singleline(&mut self.take_profit).desired_width(70.0));
+            ui.label("SL:");
+            ui.add(egui::TextEdit::singleline(&mut self.stop_loss).desired_width(70.0));
+        });
+    }
+
+    fn show_last_price_button(&mut self, ui: &mut egui::Ui, field: Field) {
+        let button = ui.add_enabled(self.market_price.is_some(), egui::Button::new("📋 Last").small())
+            .on_hover_text("Use the last price")
+            .on_disabled_hover_text("No price for this symbol yet");
+        if let Some(price) = self.market_price.filter(|_| button.clicked()) {
+            match field {
+                Field::Limit => self.price = price.to_string(),
+                Field::Stop => self.stop_price = price.to_string(),
+            }
+        }
+    }
+
+    /// BUY and SELL, which open the confirmation if the inputs are good and the order is
+    /// within the risk limits.
+    pub fn show_side_buttons(&mut self, ui: &mut egui::Ui, symbol: &str, trading: &TradingContext<'_>) {
+        let buy_button = ui.add(egui::Button::new(RichText::new("BUY").color(Color32::WHITE))
+            .fill(Color32::from_rgb(0, 150, 0)));
+        if buy_button.clicked() {
+            self.request(Side::Buy, symbol, trading);
+        }
+
+        let sell_button = ui.add(egui::Button::new(RichText::new("SELL").color(Color32::WHITE))
+            .fill(Color32::from_rgb(150, 0, 0)));
+        if sell_button.clicked() {
+            self.request(Side::Sell, symbol, trading);
+        }
+    }
+
+    /// A limit order at `price` for the form's quantity, or `default_qty` if there is none,
+    /// through the same checks as BUY and SELL. For the trading hotkeys, which leave the form
+    /// as it is, and an order waiting for confirmation too: that one is confirmed or cancelled
+    /// first.
+    pub fn request_at(
+        &mut self,
+        side: Side,
+        symbol: &str,
+        price: Option<f64>,
+        default_qty: u32,
+        trading: &TradingContext<'_>,
+    ) {
+        if self.pending.is_some() {
+            log::warn!("Hotkey {side} {symbol} ignored, an order is waiting for confirmation");
+            return;
+        }
+        let Some(price) = price else {
+            self.invalid = vec![format!("No price for {symbol} yet")];
+            return;
+        };
+        let instrument = trading.instruments.get(symbol);
+        let hotkey = OrderTicket {
+            qty: if self.qty.trim().is_empty() { default_qty.to_string() } else { self.qty.clone() },
+            price: Price::from_f64(price).round_to(instrument.tick_size).to_string(),
+            order_type: OrderType::Limit,
+            time_in_force: self.time_in_force,
+            market_price: self.market_price,
+            side,
+            ..Default::default()
+        };
+        self.confirm(hotkey.validate(symbol, instrument), false, trading);
+    }
+
+    /// The form as a limit or stop order at `price`, through the same checks as BUY and SELL.
+    /// For the chart's menu.
+    pub fn request_priced(
+        &mut self,
+        side: Side,
+        symbol: &str,
+        order_type: OrderType,
+        price: Price,
+        trading: &TradingContext<'_>,
+    ) {
+        self.order_type = order_type;
+        match order_type {
+            OrderType::Stop => self.stop_price = price.to_string(),
+            _ => self.price = price.to_string(),
+        }
+        self.request(side, symbol, trading);
+    }
+
+    /// Moves a working order's limit or stop to `price`, if what's left of it still passes the
+    /// risk limits there. For dragging its line on the chart.
+    pub fn request_move(
+        &mut self,
+        ctx: &egui::Context,
+        client_id: &str,
+        kind: LevelKind,
+        price: Price,
+        trading: &TradingContext<'_>,
+    ) {
+        let Some(order) = trading.orders.lock().unwrap().get(client_id).cloned() else {
+            return;
+        };
+        let mut spec = order.spec();
+        spec.qty = order.qty.saturating_sub(order.filled_qty);
+        match kind {
+            LevelKind::Limit => spec.limit_price = Some(price),
+            LevelKind::Stop => spec.stop_price = Some(price),
+        }
+        spec.price = spec.limit_price.or(spec.stop_price).unwrap_or(price);
+        let name = match kind {
+            LevelKind::Limit => "Limit price",
+            LevelKind::Stop => "Stop price",
+        };
+        let checked = trading.instruments.get(&order.symbol).check_price(price, name);
+        self.invalid = match checked {
+            Ok(_) => trading.risk.check(&spec, &trading.exposure(&order.symbol, self.market_price)),
+            Err(message) => vec![message],
+        };
+        if self.invalid.is_empty() {
+            orders::modify(trading.orders, trading.backend, ctx, client_id, kind, price);
+        }
+    }
+
+    /// Validates the form as a `side` order and risk checks it, opening the confirmation if
+    /// it passes. What BUY and SELL do.
+    pub fn request(&mut self, side: Side, symbol: &str, trading: &TradingContext<'_>) {
+        self.side = side;
+        let validated = self.validate(symbol, trading.instruments.get(symbol));
+        self.confirm(validated, true, trading);
+    }
+
+    /// Risk checks a validated order and opens the confirmation if it passes.
+    fn confirm(
+        &mut self,
+        validated: Result<(OrderSpec, Option<BracketSpec>), String>,
+        from_form: bool,
+        trading: &TradingContext<'_>,
+    ) {
+        self.invalid = match validated {
+            Ok((spec, bracket)) => {
+                let reasons = trading.risk.check(&spec, &trading.exposure(&spec.symbol, self.market_price));
+                if reasons.is_empty() {
+                    self.pending = Some((spec, bracket));
+                    self.pending_from_form = from_form;
+                } else {
+                    log::warn!("Risk check stopped {} {} {}: {}", spec.side, spec.qty, spec.symbol, reasons.join("; "));
+                }
+                reasons
+            }
+            Err(message) => vec![message],
+        };
+    }
+
+    /// Turns the form into an order, or says what's wrong with it. Prices have to be on the
+    /// symbol's tick and the quantity a whole number of lots.
+    fn validate(&self, symbol: &str, instrument: InstrumentSpec) -> Result<(OrderSpec, Option<BracketSpec>), String> {
+        if symbol.is_empty() {
+            return Err("Pick a symbol".to_owned());
+        }
+        let qty = match self.qty.trim().parse::<u32>() {
+            Ok(qty) => instrument.check_qty(qty)?,
+            Err(_) => return Err("Quantity must be a whole number above 0".to_owned()),
+        };
+        let limit_price = if self.order_type.has_limit() { Some(parse_price(&self.price, "Limit price", instrument)?) } else { None };
+        let stop_price = if self.order_type.has_stop() { Some(parse_price(&self.stop_price, "Stop price", instrument)?) } else { None };
+        let Some(price) = limit_price.or(stop_price).or(self.market_price) else {
+            return Err("No price for this symbol yet, use a limit order".to_owned());
+        };
+        // the risk limits may be off, this can't be
+        if price.checked_times(qty as i64).is_none() {
+            return Err(format!("{qty} at ${price} is too large an order"));
+        }
+        let spec = OrderSpec {
+            symbol: symbol.to_owned(),
+            side: self.side,
+            qty,
+            order_type: self.order_type,
+            time_in_force: self.time_in_force,
+            limit_price,
+            stop_price,
+            price,
+            leg: None,
+        };
+        if !self.bracket {
+            return Ok((spec, None));
+        }
+
+        let bracket = BracketSpec {
+            take_profit: parse_price(&self.take_profit, "Take profit", instrument)?,
+            stop_loss: parse_price(&self.stop_loss, "Stop loss", instrument)?,
+        };
+        let in_order = match self.side {
+            Side::Buy => bracket.stop_loss < price && price < bracket.take_profit,
+            Side::Sell => bracket.take_profit < price && price < bracket.stop_loss,
+        };
+        if !in_order {
+            let (above, below) = match self.side {
+                Side::Buy => ("take profit", "stop loss"),
+                Side::Sell => ("stop loss", "take profit"),
+            };
+            return Err(format!("The {above} has to be above ${price} and the {below} below it"));
+        }
+        Ok((spec, Some(bracket)))
+    }
+
+    /// Validation problems and the status of the last order sent from this ticket. Clears the
+    /// form once the server has the order, a rejected one keeps it around for fixing up.
+    pub fn show_feedback(&mut self, ui: &mut egui::Ui, trading: &TradingContext<'_>) {
+        for message in &self.invalid {
+            ui.label(RichText::new(format!("⚠ {message}")).small().color(Color32::from_rgb(255, 100, 100)));
+        }
+
+        let Some(client_id) = self.submitted.clone() else {
+            return;
+        };
+        let Some(order) = trading.orders.lock().unwrap().get(&client_id).cloned() else {
+            self.submitted = None;
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{} {} {} {}", order.client_id, order.side, order.qty, order.symbol)).small());
+            ui.label(RichText::new(order.status.label()).small().color(order.status.color()));
+            match &order.status {
+                OrderStatus::Pending => {
+                    ui.spinner();
+                }
+                OrderStatus::Unconfirmed(reason) => {
+                    ui.spinner().on_hover_text(format!("{reason}, checking whether the server has it. Don't send it again."));
+                }
+                OrderStatus::Rejected(reason) => {
+                    ui.label(RichText::new(reason).small().color(Color32::from_rgb(255, 100, 100)));
+                }
+                _ => {}
+            }
+            if !order.status.is_open() && ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                self.submitted = None;
+            }
+        });
+
+        if self.awaiting_ack && !matches!(order.status, OrderStatus::Pending | OrderStatus::Unconfirmed(_)) {
+            self.awaiting_ack = false;
+            if !matches!(order.status, OrderStatus::Rejected(_)) {
+                self.qty.clear();
+                self.price.clear();
+                self.stop_price.clear();
+                self.take_profit.clear();
+                self.stop_loss.clear();
+            }
+        }
+    }
+
+    /// The confirmation dialog, if BUY or SELL was clicked. `id` keeps tickets for the same
+    /// symbol apart. Enter confirms and Escape cancels while the dialog is in front.
+    pub fn show_confirmation(
+        &mut self,
+        ctx: &egui::Context,
+        id: egui::Id,
+        trading: &TradingContext<'_>,
+    ) {
+        let Some((spec, bracket)) = self.pending.clone() else {
+            return;
+        };
+
+        let in_front = ctx.top_layer_id() == Some(egui::LayerId::new(egui::Order::Middle, id));
+        let (enter, escape) = if in_front {
+            ctx.input_mut(|i| (i.consume_key(egui::Modifiers::NONE, egui::Key::Enter), i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)))
+        } else {
+            (false, false)
+        };
+
+        egui::Window::new("🔔 Confirm Order")
+            .id(id)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("Order Confirmation").size(16.0).strong());
+                ui.separator();
+
+                ui.label(format!("Symbol: {}", spec.symbol));
+                ui.label(RichText::new(format!("Side: {}", spec.side)).color(spec.side.color()));
+                ui.label(format!("Type: {} ({})", spec.order_type.label(), spec.time_in_force.label()))
+                    .on_hover_text(spec.time_in_force.description());
+                ui.label(format!("Quantity: {}", spec.qty));
+                if let Some(stop) = spec.stop_price {
+                    ui.label(format!("Stop: ${stop}"));
+                }
+                if let Some(limit) = spec.limit_price {
+                    ui.label(format!("Limit: ${limit}"));
+                }
+                if let Some(bracket) = bracket {
+                    ui.label(RichText::new(format!("Take profit: ${}", bracket.take_profit)).color(Color32::from_rgb(0, 200, 0)));
+                    ui.label(RichText::new(format!("Stop loss: ${}", bracket.stop_loss)).color(Color32::from_rgb(220, 0, 0)));
+                }
+
+                let total = spec.price.times(spec.qty as i64);
+                match spec.order_type {
+                    OrderType::Limit => ui.label(format!("Total: ${total}")),
+                    _ => ui.label(format!("Est. total: ${total}")),
+                };
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let confirm_button = ui.add(egui::Button::new(RichText::new("✅ Confirm").color(Color32::WHITE))
+                        .fill(Color32::from_rgb(0, 150, 0)))
+                        .on_hover_text("Enter");
+                    if confirm_button.clicked() || enter {
+                        // the form is cleared by show_feedback once the order is accepted
+                        let client_id = match bracket {
+                            Some(bracket) => orders::submit_bracket(trading.orders, trading.backend, ctx, spec.clone(), bracket),
+                            None => orders::submit(trading.orders, trading.backend, ctx, spec.clone()),
+                        };
+                        // a hotkey order doesn't take the status line from a form order still
+                        // waiting for its answer
+                        if self.pending_from_form || !self.awaiting_ack {
+                            self.submitted = Some(client_id);
+                            self.awaiting_ack = self.pending_from_form;
+                        }
+                        self.pending = None;
+                    }
+
+                    let cancel_button = ui.add(egui::Button::new(RichText::new("❌ Cancel").color(Color32::WHITE))
+                        .fill(Color32::from_rgb(150, 0, 0)))
+                        .on_hover_text("Esc");
+                    if cancel_button.clicked() || escape {
+                        self.pending = None;
+                    }
+                });
+            });
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Limit,
+    Stop,
+}
+
+fn parse_price(text: &str, name: &str, instrument: InstrumentSpec) -> Result<Price, String> {
+    let price = Price::parse(text).map_err(|e| format!("{name}: {e}"))?;
+    instrument.check_price(price, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockBackend;
+
+    struct Setup {
+        backend: MockBackend,
+        orders: Arc<Mutex<OrderBook>>,
+        risk: RiskLimits,
+        portfolio: Portfolio,
+        instruments: Instruments,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            Self {
+                backend: MockBackend::default(),
+                orders: Arc::default(),
+                risk: RiskLimits::default(),
+                portfolio: Portfolio::default(),
+                instruments: Instruments::default(),
+            }
+        }
+
+        fn trading(&self) -> TradingContext<'_> {
+            TradingContext {
+                backend: &self.backend,
+                orders: &self.orders,
+                risk: &self.risk,
+                portfolio: &self.portfolio,
+                instruments: &self.instruments,
+            }
+        }
+    }
+
+    fn form(qty: &str, price: &str) -> OrderTicket {
+        OrderTicket { qty: qty.to_owned(), price: price.to_owned(), order_type: OrderType::Limit, ..Default::default() }
+    }
+
+    #[test]
+    fn a_hotkey_leaves_a_staged_order_alone() {
+        let setup = Setup::new();
+        let mut ticket = form("100", "100");
+        ticket.request(Side::Buy, "AAPL", &setup.trading());
+        let staged = ticket.pending.clone();
+        assert!(staged.is_some(), "{:?}", ticket.invalid);
+
+        ticket.request_at(Side::Sell, "AAPL", Some(101.0), 10, &setup.trading());
+        assert_eq!(ticket.pending, staged);
+        assert!(ticket.pending_from_form);
+        assert!(ticket.invalid.is_empty());
+    }
+
+    #[test]
+    fn a_hotkey_order_leaves_the_form_alone() {
+        let setup = Setup::new();
+        let mut ticket = form("", "99.5");
+        ticket.request_at(Side::Sell, "AAPL", Some(101.0), 10, &setup.trading());
+
+        let (spec, bracket) = ticket.pending.clone().unwrap();
+        assert_eq!((spec.side, spec.qty, spec.limit_price), (Side::Sell, 10, Some(Price::from_cents(10_100))));
+        assert_eq!(bracket, None);
+        assert!(!ticket.pending_from_form);
+        assert_eq!((ticket.qty.as_str(), ticket.price.as_str()), ("", "99.5"));
+    }
+}